@@ -0,0 +1,150 @@
+//! Merkle airdrops.
+//!
+//! The owner funds a campaign with `create_airdrop`, which moves the whole amount to the contract
+//! account and stores the merkle root of the `(account_id, amount)` allocations. Each leaf is
+//! `sha256(borsh((account_id, amount)))` with `amount` as a `u128`, and each parent is the sha256 of
+//! its two children concatenated in ascending order, so proofs don't need to encode positions.
+//! Registered accounts claim their allocation once with `claim` until the campaign expires, after
+//! which the owner can sweep whatever is left back with `sweep_airdrop`.
+use crate::*;
+use near_sdk::json_types::{Base64VecU8, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AirdropCampaign {
+    pub merkle_root: Base64VecU8,
+    pub total_amount: U128,
+    pub claimed_amount: U128,
+    /// Timestamp after which nothing can be claimed, in nanoseconds.
+    pub expires_at: U64,
+    /// Whether the unclaimed amount was swept back to the owner.
+    pub swept: bool,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Creates an airdrop campaign funded with `total_amount` tokens from the owner and returns
+    /// its id. Only the owner can call this.
+    #[payable]
+    pub fn create_airdrop(
+        &mut self,
+        merkle_root: Base64VecU8,
+        total_amount: U128,
+        expires_at: U64,
+    ) -> u64 {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(merkle_root.0.len() == 32, "The merkle root has to be 32 bytes");
+        require!(total_amount.0 > 0, "The amount should be a positive number");
+        require!(expires_at.0 > env::block_timestamp(), "The expiry should be in the future");
+        let contract_id = env::current_account_id();
+        if !self.token.accounts.contains_key(&contract_id) {
+            self.token.internal_register_account(&contract_id);
+        }
+        let owner_id = self.owner_id.clone();
+        self.internal_transfer(
+            &owner_id,
+            &contract_id,
+            total_amount.0,
+            Some("airdrop".to_string()),
+        );
+        self.airdrops.push(&AirdropCampaign {
+            merkle_root,
+            total_amount,
+            claimed_amount: U128(0),
+            expires_at,
+            swept: false,
+        });
+        self.airdrops.len() - 1
+    }
+
+    /// Transfers the caller's allocation of campaign `campaign_id`, given the merkle `proof` of the
+    /// `(caller, amount)` leaf. Each account can claim once per campaign, while transfers aren't
+    /// paused.
+    pub fn claim(&mut self, campaign_id: u64, amount: U128, proof: Vec<Base64VecU8>) {
+        self.assert_not_paused(PausableOperation::Transfer);
+        let account_id = env::predecessor_account_id();
+        let mut campaign = self.internal_get_airdrop(campaign_id);
+        require!(env::block_timestamp() < campaign.expires_at.0, "The airdrop has expired");
+        require!(
+            verify_merkle_proof(&campaign.merkle_root.0, &account_id, amount.0, &proof),
+            "Invalid merkle proof"
+        );
+        require!(
+            self.airdrop_claims.insert(&(campaign_id, account_id.clone())),
+            "The airdrop is already claimed"
+        );
+        campaign.claimed_amount = U128(campaign.claimed_amount.0 + amount.0);
+        require!(campaign.claimed_amount.0 <= campaign.total_amount.0, "The airdrop is exhausted");
+        self.airdrops.replace(campaign_id, &campaign);
+        let contract_id = env::current_account_id();
+        self.internal_transfer(&contract_id, &account_id, amount.0, Some("airdrop".to_string()));
+    }
+
+    /// Transfers the unclaimed amount of an expired campaign back to the owner. Only the owner
+    /// can call this.
+    #[payable]
+    pub fn sweep_airdrop(&mut self, campaign_id: u64) -> U128 {
+        assert_one_yocto();
+        self.assert_owner();
+        let mut campaign = self.internal_get_airdrop(campaign_id);
+        require!(env::block_timestamp() >= campaign.expires_at.0, "The airdrop hasn't expired yet");
+        require!(!campaign.swept, "The airdrop is already swept");
+        campaign.swept = true;
+        self.airdrops.replace(campaign_id, &campaign);
+        let unclaimed = campaign.total_amount.0 - campaign.claimed_amount.0;
+        if unclaimed > 0 {
+            let contract_id = env::current_account_id();
+            let owner_id = self.owner_id.clone();
+            self.internal_transfer(&contract_id, &owner_id, unclaimed, Some("airdrop".to_string()));
+        }
+        unclaimed.into()
+    }
+
+    pub fn get_airdrop(&self, campaign_id: u64) -> Option<AirdropCampaign> {
+        self.airdrops.get(campaign_id)
+    }
+
+    pub fn get_airdrops(
+        &self,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<AirdropCampaign> {
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+        self.airdrops
+            .iter()
+            .skip(start as usize)
+            .take(limit.unwrap_or(self.airdrops.len()) as usize)
+            .collect()
+    }
+
+    pub fn is_airdrop_claimed(&self, campaign_id: u64, account_id: AccountId) -> bool {
+        self.airdrop_claims.contains(&(campaign_id, account_id))
+    }
+}
+
+impl Contract {
+    fn internal_get_airdrop(&self, campaign_id: u64) -> AirdropCampaign {
+        self.airdrops
+            .get(campaign_id)
+            .unwrap_or_else(|| env::panic_str("The airdrop doesn't exist"))
+    }
+}
+
+fn verify_merkle_proof(
+    root: &[u8],
+    account_id: &AccountId,
+    amount: Balance,
+    proof: &[Base64VecU8],
+) -> bool {
+    let leaf = env::sha256(&(account_id, amount).try_to_vec().unwrap());
+    let computed_root = proof.iter().fold(leaf, |node, sibling| {
+        if node <= sibling.0 {
+            env::sha256(&[node, sibling.0.clone()].concat())
+        } else {
+            env::sha256(&[sibling.0.clone(), node].concat())
+        }
+    });
+    computed_root == root
+}
@@ -0,0 +1,65 @@
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Allows `spender_id` to move up to `amount` tokens out of the caller's balance with
+    /// `ft_transfer_from`. Replaces any previous allowance; an `amount` of 0 removes it.
+    ///
+    /// Requires at least 1 yoctoNEAR. A new allowance entry has to be paid for with the attached
+    /// deposit, unused deposit and released storage are refunded.
+    #[payable]
+    pub fn ft_approve(&mut self, spender_id: AccountId, amount: U128) {
+        require!(env::attached_deposit() > 0, "Requires attached deposit of at least 1 yoctoNEAR");
+        let owner_id = env::predecessor_account_id();
+        require!(owner_id != spender_id, "The owner can't approve itself");
+        self.token.internal_unwrap_balance_of(&owner_id);
+
+        let initial_storage_usage = env::storage_usage();
+        let key = (owner_id, spender_id);
+        if amount.0 > 0 {
+            self.allowances.insert(&key, &amount.0);
+        } else {
+            self.allowances.remove(&key);
+        }
+        refund_deposit(initial_storage_usage);
+    }
+
+    /// Returns how many tokens `spender_id` can still transfer on behalf of `owner_id`.
+    pub fn ft_allowance(&self, owner_id: AccountId, spender_id: AccountId) -> U128 {
+        self.allowances.get(&(owner_id, spender_id)).unwrap_or(0).into()
+    }
+
+    /// Transfers `amount` tokens from `owner_id` to `receiver_id`, spending the allowance that
+    /// `owner_id` gave to the caller. Requires exactly 1 yoctoNEAR.
+    #[payable]
+    pub fn ft_transfer_from(
+        &mut self,
+        owner_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+    ) {
+        assert_one_yocto();
+        self.assert_not_paused(PausableOperation::Transfer);
+        let key = (owner_id, env::predecessor_account_id());
+        let allowance = self.allowances.get(&key).unwrap_or(0);
+        require!(
+            amount.0 <= allowance,
+            format!("The amount exceeds the allowance of {}", allowance)
+        );
+        let remaining = allowance - amount.0;
+        if remaining > 0 {
+            self.allowances.insert(&key, &remaining);
+        } else {
+            // The entry was paid for by the owner, so its storage is given back to the owner.
+            let initial_storage_usage = env::storage_usage();
+            self.allowances.remove(&key);
+            let released = Balance::from(initial_storage_usage - env::storage_usage())
+                * env::storage_byte_cost();
+            if released > 0 {
+                Promise::new(key.0.clone()).transfer(released);
+            }
+        }
+        self.internal_transfer_with_fee(&key.0, &receiver_id, amount.0, memo);
+    }
+}
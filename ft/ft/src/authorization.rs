@@ -0,0 +1,106 @@
+//! Gasless transfers signed off-chain.
+//!
+//! An account registers an ed25519 public key with `set_authorization_key`. It can then sign a
+//! `TransferAuthorization` off-chain, and anyone (usually a relayer paying for gas) can submit it
+//! with `ft_transfer_with_authorization`. The signed message is the borsh serialization of
+//! `(contract_id, authorization)`, so a signature can't be replayed on another token contract.
+//! Nonces are sequential per account, which makes every authorization usable only once.
+use crate::pause::PausableOperation;
+use crate::*;
+use ed25519_dalek::Verifier;
+use near_sdk::json_types::{Base64VecU8, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{CurveType, PublicKey};
+use std::convert::TryFrom;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TransferAuthorization {
+    pub from: AccountId,
+    pub to: AccountId,
+    pub amount: U128,
+    /// Must equal `ft_authorization_nonce(from)`.
+    pub nonce: U64,
+    /// Timestamp after which the authorization can't be used, in nanoseconds.
+    pub deadline: U64,
+    pub memo: Option<String>,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Registers the ed25519 key that signs the caller's transfer authorizations, replacing any
+    /// previous one. The attached deposit has to cover any new storage.
+    #[payable]
+    pub fn set_authorization_key(&mut self, public_key: PublicKey) {
+        require!(public_key.curve_type() == CurveType::ED25519, "Only ed25519 keys are supported");
+        let initial_storage_usage = env::storage_usage();
+        self.authorization_keys.insert(&env::predecessor_account_id(), &public_key);
+        refund_deposit(initial_storage_usage);
+    }
+
+    /// Removes the caller's authorization key, so none of its pending authorizations can be used.
+    /// The storage deposit of the key is refunded.
+    #[payable]
+    pub fn remove_authorization_key(&mut self) {
+        assert_one_yocto();
+        let initial_storage_usage = env::storage_usage();
+        require!(
+            self.authorization_keys.remove(&env::predecessor_account_id()).is_some(),
+            "The account has no authorization key"
+        );
+        refund_deposit(initial_storage_usage);
+    }
+
+    pub fn get_authorization_key(&self, account_id: AccountId) -> Option<PublicKey> {
+        self.authorization_keys.get(&account_id)
+    }
+
+    /// Returns the nonce the next authorization of `account_id` must use.
+    pub fn ft_authorization_nonce(&self, account_id: AccountId) -> U64 {
+        self.authorization_nonces.get(&account_id).unwrap_or(0).into()
+    }
+
+    /// Transfers tokens on behalf of `authorization.from`, which signed `authorization` with its
+    /// registered key. `signature` is the 64 bytes ed25519 signature.
+    pub fn ft_transfer_with_authorization(
+        &mut self,
+        authorization: TransferAuthorization,
+        signature: Base64VecU8,
+    ) {
+        self.assert_not_paused(PausableOperation::Transfer);
+        require!(
+            env::block_timestamp() <= authorization.deadline.0,
+            "The authorization has expired"
+        );
+        let nonce = self.authorization_nonces.get(&authorization.from).unwrap_or(0);
+        require!(authorization.nonce.0 == nonce, "Invalid nonce");
+        self.assert_valid_authorization_signature(&authorization, &signature.0);
+        self.authorization_nonces.insert(&authorization.from, &(nonce + 1));
+        self.internal_transfer_with_fee(
+            &authorization.from,
+            &authorization.to,
+            authorization.amount.0,
+            authorization.memo,
+        );
+    }
+}
+
+impl Contract {
+    fn assert_valid_authorization_signature(
+        &self,
+        authorization: &TransferAuthorization,
+        signature: &[u8],
+    ) {
+        let public_key = self
+            .authorization_keys
+            .get(&authorization.from)
+            .unwrap_or_else(|| env::panic_str("The account has no authorization key"));
+        // The first byte is the curve type.
+        let public_key = ed25519_dalek::PublicKey::from_bytes(&public_key.as_bytes()[1..])
+            .unwrap_or_else(|_| env::panic_str("Invalid authorization key"));
+        let signature = ed25519_dalek::Signature::try_from(signature)
+            .unwrap_or_else(|_| env::panic_str("Invalid signature"));
+        let message = (env::current_account_id(), authorization).try_to_vec().unwrap();
+        require!(public_key.verify(&message, &signature).is_ok(), "Invalid signature");
+    }
+}
@@ -0,0 +1,86 @@
+use crate::pause::PausableOperation;
+use crate::*;
+use near_contract_standards::fungible_token::events::FtTransfer;
+
+/// Upper bound for the number of transfers of `ft_batch_transfer`, which keeps its gas and the
+/// size of its `FtTransfer` log in check.
+const MAX_BATCH_TRANSFERS: usize = 50;
+
+#[near_bindgen]
+impl Contract {
+    /// Transfers tokens from the caller to every receiver of `transfers` within one receipt, so
+    /// either all of them go through or none does. The call fails if a receiver isn't
+    /// registered, unless `register_receivers` is set: unregistered receivers are then registered
+    /// with the attached deposit, and whatever is left above one yocto is refunded. Logs a single
+    /// `FtTransfer` event listing every transfer.
+    #[payable]
+    pub fn ft_batch_transfer(
+        &mut self,
+        transfers: Vec<(AccountId, U128)>,
+        memo: Option<String>,
+        register_receivers: Option<bool>,
+    ) {
+        self.assert_not_paused(PausableOperation::Transfer);
+        require!(!transfers.is_empty(), "There are no transfers");
+        require!(
+            transfers.len() <= MAX_BATCH_TRANSFERS,
+            format!("A batch can't have more than {} transfers", MAX_BATCH_TRANSFERS)
+        );
+        let sender_id = env::predecessor_account_id();
+        let mut refund = 0;
+        if register_receivers.unwrap_or(false) {
+            let min_balance = self.token.storage_balance_bounds().min.0;
+            let mut storage_cost = 0;
+            for (receiver_id, _) in transfers.iter() {
+                if !self.token.accounts.contains_key(receiver_id) {
+                    self.token.internal_register_account(receiver_id);
+                    storage_cost += min_balance;
+                }
+            }
+            require!(
+                env::attached_deposit() > storage_cost,
+                format!(
+                    "The attached deposit should be at least {} to register the receivers",
+                    storage_cost + 1
+                )
+            );
+            refund = env::attached_deposit() - storage_cost - 1;
+        } else {
+            assert_one_yocto();
+            for (receiver_id, _) in transfers.iter() {
+                require!(
+                    self.token.accounts.contains_key(receiver_id),
+                    format!("The account {} is not registered", receiver_id)
+                );
+            }
+        }
+
+        let mut logged_transfers = Vec::with_capacity(transfers.len());
+        for (receiver_id, amount) in transfers {
+            let mut net_amount = amount.0;
+            if let Some((treasury_id, fee)) =
+                self.internal_transfer_fee(&sender_id, &receiver_id, amount.0)
+            {
+                self.internal_transfer_unlogged(&sender_id, &treasury_id, fee);
+                logged_transfers.push((treasury_id, U128(fee), Some("fee")));
+                net_amount -= fee;
+            }
+            self.internal_transfer_unlogged(&sender_id, &receiver_id, net_amount);
+            logged_transfers.push((receiver_id, U128(net_amount), memo.as_deref()));
+        }
+        let events: Vec<FtTransfer> = logged_transfers
+            .iter()
+            .map(|(receiver_id, amount, memo)| FtTransfer {
+                old_owner_id: &sender_id,
+                new_owner_id: receiver_id,
+                amount,
+                memo: *memo,
+            })
+            .collect();
+        FtTransfer::emit_many(&events);
+
+        if refund > 0 {
+            Promise::new(sender_id).transfer(refund);
+        }
+    }
+}
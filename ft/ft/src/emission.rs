@@ -0,0 +1,156 @@
+//! Hard supply cap and scheduled emission.
+//!
+//! The owner sets the emission schedule once. Each epoch releases a fixed amount to every pool of
+//! the schedule; the first epoch is due at `start`. Anyone can call `release_emission` to mint
+//! whatever is due by the current block timestamp. Emission never takes the total supply above the
+//! maximum supply: whatever would exceed it is dropped. The owner can replace the account of a
+//! pool, for instance one that unregistered or got frozen, and raise or lower the maximum supply
+//! down to the current total supply.
+use crate::*;
+use near_sdk::json_types::U64;
+use near_sdk::serde::{Deserialize, Serialize};
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EmissionPool {
+    /// Registered account that receives the emission.
+    pub account_id: AccountId,
+    pub amount_per_epoch: U128,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EmissionSchedule {
+    /// Timestamp of the first epoch, in nanoseconds.
+    pub start: U64,
+    /// Duration of an epoch, in nanoseconds.
+    pub epoch_duration: U64,
+    pub epochs: u32,
+    pub pools: Vec<EmissionPool>,
+}
+
+impl EmissionSchedule {
+    fn amount_per_epoch(&self) -> Balance {
+        self.pools.iter().map(|pool| pool.amount_per_epoch.0).sum()
+    }
+
+    /// Number of epochs that are due by `timestamp`.
+    fn epochs_due(&self, timestamp: u64) -> u32 {
+        if timestamp < self.start.0 {
+            return 0;
+        }
+        let epochs_due = (timestamp - self.start.0) / self.epoch_duration.0 + 1;
+        std::cmp::min(epochs_due, u64::from(self.epochs)) as u32
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the maximum supply, if the token has one.
+    pub fn ft_max_supply(&self) -> Option<U128> {
+        self.max_supply.map(U128)
+    }
+
+    pub fn get_emission_schedule(&self) -> Option<EmissionSchedule> {
+        self.emission_schedule.clone()
+    }
+
+    /// Returns the cumulative amount minted by `release_emission`.
+    pub fn get_emitted_amount(&self) -> U128 {
+        self.emitted_amount.into()
+    }
+
+    /// Sets the maximum supply, which can't be below the current total supply. Only the owner can
+    /// call this.
+    #[payable]
+    pub fn set_max_supply(&mut self, max_supply: U128) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(
+            max_supply.0 >= self.token.total_supply,
+            "The maximum supply can't be below the total supply"
+        );
+        self.max_supply = Some(max_supply.0);
+    }
+
+    /// Replaces the account that receives the emission of the pool at `pool_index`. Only the owner
+    /// can call this.
+    #[payable]
+    pub fn set_emission_pool_account(&mut self, pool_index: u32, account_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(
+            self.token.accounts.contains_key(&account_id),
+            format!("The pool account {} is not registered", account_id)
+        );
+        let schedule = self
+            .emission_schedule
+            .as_mut()
+            .unwrap_or_else(|| env::panic_str("The emission schedule is not set"));
+        let pool = schedule
+            .pools
+            .get_mut(pool_index as usize)
+            .unwrap_or_else(|| env::panic_str("The pool doesn't exist"));
+        pool.account_id = account_id;
+    }
+
+    /// Sets the emission schedule. It can only be set once, and the whole schedule has to fit
+    /// under the maximum supply. Only the owner can call this.
+    #[payable]
+    pub fn set_emission_schedule(&mut self, schedule: EmissionSchedule) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.assert_not_wrapped_near();
+        require!(self.emission_schedule.is_none(), "The emission schedule is already set");
+        require!(schedule.epoch_duration.0 > 0, "The epoch duration should be positive");
+        require!(schedule.epochs > 0, "The number of epochs should be positive");
+        require!(!schedule.pools.is_empty(), "The schedule needs at least one pool");
+        for pool in schedule.pools.iter() {
+            require!(pool.amount_per_epoch.0 > 0, "The pool amount should be positive");
+            require!(
+                self.token.accounts.contains_key(&pool.account_id),
+                format!("The pool account {} is not registered", pool.account_id)
+            );
+        }
+        let total_emission = schedule
+            .amount_per_epoch()
+            .checked_mul(Balance::from(schedule.epochs))
+            .unwrap_or_else(|| env::panic_str("Emission overflow"));
+        if let Some(max_supply) = self.max_supply {
+            require!(
+                self.token.total_supply.saturating_add(total_emission) <= max_supply,
+                "The emission schedule exceeds the maximum supply"
+            );
+        }
+        self.emission_schedule = Some(schedule);
+    }
+
+    /// Mints every emission that is due by the current block timestamp to the pools. Anyone can
+    /// call this. Returns the amount minted.
+    pub fn release_emission(&mut self) -> U128 {
+        self.assert_not_wrapped_near();
+        let schedule = self
+            .emission_schedule
+            .clone()
+            .unwrap_or_else(|| env::panic_str("The emission schedule is not set"));
+        let epochs_due = schedule.epochs_due(env::block_timestamp());
+        if epochs_due <= self.released_epochs {
+            return U128(0);
+        }
+        let epochs = Balance::from(epochs_due - self.released_epochs);
+        let mut released = 0;
+        for pool in schedule.pools.iter() {
+            let mut amount = pool.amount_per_epoch.0 * epochs;
+            if let Some(max_supply) = self.max_supply {
+                amount = std::cmp::min(amount, max_supply.saturating_sub(self.token.total_supply));
+            }
+            if amount > 0 {
+                self.internal_mint(&pool.account_id, amount, Some("emission"));
+                released += amount;
+            }
+        }
+        self.released_epochs = epochs_due;
+        self.emitted_amount += released;
+        released.into()
+    }
+}
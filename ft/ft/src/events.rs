@@ -0,0 +1,114 @@
+//! Contract-specific events, logged in the same [NEP-297] format as the standard `FtMint`,
+//! `FtTransfer` and `FtBurn` events.
+//!
+//! [NEP-297]: https://nomicon.io/Standards/EventsFormat
+use crate::pause::PausableOperation;
+use near_sdk::serde::Serialize;
+use near_sdk::serde_json::json;
+use near_sdk::{env, AccountId};
+
+const EVENT_STANDARD: &str = "realities";
+const EVENT_STANDARD_VERSION: &str = "1.0.0";
+
+fn emit_event<T: Serialize>(event: &str, data: &[T]) {
+    let event = json!({
+        "standard": EVENT_STANDARD,
+        "version": EVENT_STANDARD_VERSION,
+        "event": event,
+        "data": data,
+    });
+    env::log_str(&format!("EVENT_JSON:{}", event));
+}
+
+/// Data to log when the set of paused operations changes.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PauseChanged<'a> {
+    pub account_id: &'a AccountId,
+    pub paused_operations: &'a [PausableOperation],
+}
+
+impl PauseChanged<'_> {
+    pub fn emit(self) {
+        emit_event("pause_changed", &[self])
+    }
+}
+
+/// Data to log when the owner takes a balance snapshot.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SnapshotCreated {
+    pub snapshot_id: u64,
+    pub block_height: u64,
+}
+
+impl SnapshotCreated {
+    pub fn emit(self) {
+        emit_event("snapshot_created", &[self])
+    }
+}
+
+/// Data to log when an account changes the delegate of its voting power.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DelegateChanged<'a> {
+    pub delegator: &'a AccountId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_delegate: Option<&'a AccountId>,
+    pub to_delegate: &'a AccountId,
+}
+
+impl DelegateChanged<'_> {
+    pub fn emit(self) {
+        emit_event("delegate_changed", &[self])
+    }
+}
+
+/// Data to log when the owner freezes an account.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountFrozen<'a> {
+    pub account_id: &'a AccountId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<&'a str>,
+}
+
+impl AccountFrozen<'_> {
+    pub fn emit(self) {
+        emit_event("account_frozen", &[self])
+    }
+}
+
+/// Data to log when the owner unfreezes an account.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountUnfrozen<'a> {
+    pub account_id: &'a AccountId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<&'a str>,
+}
+
+impl AccountUnfrozen<'_> {
+    pub fn emit(self) {
+        emit_event("account_unfrozen", &[self])
+    }
+}
+
+/// Data to log when the owner updates the token metadata.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FtMetadataUpdate<'a> {
+    pub fields: &'a [&'a str],
+}
+
+impl FtMetadataUpdate<'_> {
+    pub fn emit(self) {
+        emit_event("ft_metadata_update", &[self])
+    }
+}
@@ -0,0 +1,169 @@
+//! Transfer fee routed to a treasury.
+//!
+//! The fee is taken out of the transferred amount, so the receiver gets `amount - fee`. Transfers
+//! from or to an exempt account (and the treasury itself) are free. For `ft_transfer_call` the
+//! receiver is only told about the net amount, and `ft_resolve_transfer` gives the sender back the
+//! share of the fee matching what the receiver didn't use, so the fee is only paid on the amount
+//! actually used.
+use crate::math::mul_div;
+use crate::*;
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// Upper bound for `TransferFee::fee_bps`, 10%.
+const MAX_TRANSFER_FEE_BPS: u16 = 1_000;
+const BPS_DENOMINATOR: Balance = 10_000;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TransferFee {
+    /// Fee in basis points of the transferred amount.
+    pub fee_bps: u16,
+    /// Registered account that receives the fees.
+    pub treasury_id: AccountId,
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn get_transfer_fee(&self) -> Option<TransferFee> {
+        self.transfer_fee.clone()
+    }
+
+    /// Sets the transfer fee, or disables it with `None`. Only the owner can call this.
+    #[payable]
+    pub fn set_transfer_fee(&mut self, transfer_fee: Option<TransferFee>) {
+        assert_one_yocto();
+        self.assert_owner();
+        if let Some(transfer_fee) = transfer_fee.as_ref() {
+            require!(
+                transfer_fee.fee_bps <= MAX_TRANSFER_FEE_BPS,
+                format!("The fee can't exceed {} basis points", MAX_TRANSFER_FEE_BPS)
+            );
+            require!(
+                self.token.accounts.contains_key(&transfer_fee.treasury_id),
+                "The treasury account is not registered"
+            );
+        }
+        self.transfer_fee = transfer_fee;
+    }
+
+    /// Exempts transfers from or to `account_id` from the fee. Only the owner can call this.
+    #[payable]
+    pub fn add_fee_exemption(&mut self, account_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(self.fee_exempt_accounts.insert(&account_id), "The account is already exempt");
+    }
+
+    /// Removes the fee exemption of `account_id`. Only the owner can call this.
+    #[payable]
+    pub fn remove_fee_exemption(&mut self, account_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(self.fee_exempt_accounts.remove(&account_id), "The account is not exempt");
+    }
+
+    pub fn is_fee_exempt(&self, account_id: AccountId) -> bool {
+        self.fee_exempt_accounts.contains(&account_id)
+    }
+
+    pub fn get_fee_exemptions(
+        &self,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<AccountId> {
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+        self.fee_exempt_accounts
+            .iter()
+            .skip(start as usize)
+            .take(limit.unwrap_or(self.fee_exempt_accounts.len()) as usize)
+            .collect()
+    }
+}
+
+impl Contract {
+    /// Transfers `amount` minus the fee to `receiver_id` and the fee to the treasury.
+    /// Returns the treasury and the fee taken, if any.
+    pub(crate) fn internal_transfer_with_fee(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+        memo: Option<String>,
+    ) -> Option<(AccountId, Balance)> {
+        let mut net_amount = amount;
+        let transfer_fee = self.internal_transfer_fee(sender_id, receiver_id, amount);
+        if let Some((treasury_id, fee)) = transfer_fee.as_ref() {
+            self.internal_transfer(sender_id, treasury_id, *fee, Some("fee".to_string()));
+            net_amount -= fee;
+        }
+        self.internal_transfer(sender_id, receiver_id, net_amount, memo);
+        transfer_fee
+    }
+
+    /// Gives `sender_id` back the share of the `fee` it paid on an `ft_transfer_call` of `amount`
+    /// that matches the `refund_amount` the receiver didn't use, as far as the treasury still
+    /// holds it.
+    pub(crate) fn internal_refund_fee(
+        &mut self,
+        sender_id: &AccountId,
+        treasury_id: &AccountId,
+        fee: Balance,
+        amount: Balance,
+        refund_amount: Balance,
+    ) {
+        if self.frozen_accounts.contains(treasury_id) {
+            log!("The fee refund is blocked by the frozen treasury");
+            return;
+        }
+        let treasury_balance = self.token.accounts.get(treasury_id).unwrap_or(0);
+        let fee_refund = std::cmp::min(treasury_balance, mul_div(fee, refund_amount, amount));
+        if fee_refund > 0 {
+            self.internal_before_refund(Some(treasury_id), Some(sender_id), fee_refund);
+            self.token.internal_transfer(
+                treasury_id,
+                sender_id,
+                fee_refund,
+                Some("fee refund".into()),
+            );
+        }
+    }
+
+    /// Returns the treasury and the fee to take on a transfer, if any.
+    pub(crate) fn internal_transfer_fee(
+        &self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+    ) -> Option<(AccountId, Balance)> {
+        let TransferFee { fee_bps, treasury_id } = self.transfer_fee.clone()?;
+        if self.is_fee_exempt_transfer(sender_id, receiver_id, &treasury_id) {
+            return None;
+        }
+        let fee = amount * Balance::from(fee_bps) / BPS_DENOMINATOR;
+        if fee > 0 {
+            Some((treasury_id, fee))
+        } else {
+            None
+        }
+    }
+
+    /// Fees are deposited into the treasury, so it can't unregister while the fee is on.
+    pub(crate) fn assert_not_treasury(&self, account_id: &AccountId) {
+        require!(
+            self.transfer_fee.as_ref().map(|fee| &fee.treasury_id) != Some(account_id),
+            "The treasury can't unregister while the transfer fee is on"
+        );
+    }
+
+    fn is_fee_exempt_transfer(
+        &self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        treasury_id: &AccountId,
+    ) -> bool {
+        sender_id == treasury_id
+            || receiver_id == treasury_id
+            || self.fee_exempt_accounts.contains(sender_id)
+            || self.fee_exempt_accounts.contains(receiver_id)
+    }
+}
@@ -0,0 +1,49 @@
+use crate::events::{AccountFrozen, AccountUnfrozen};
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Blocks `account_id` from sending or receiving tokens. Only the owner can call this.
+    #[payable]
+    pub fn freeze_account(&mut self, account_id: AccountId, memo: Option<String>) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(self.frozen_accounts.insert(&account_id), "The account is already frozen");
+        AccountFrozen { account_id: &account_id, memo: memo.as_deref() }.emit();
+    }
+
+    /// Lifts the block on `account_id`. Only the owner can call this.
+    #[payable]
+    pub fn unfreeze_account(&mut self, account_id: AccountId, memo: Option<String>) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(self.frozen_accounts.remove(&account_id), "The account is not frozen");
+        AccountUnfrozen { account_id: &account_id, memo: memo.as_deref() }.emit();
+    }
+
+    pub fn is_frozen(&self, account_id: AccountId) -> bool {
+        self.frozen_accounts.contains(&account_id)
+    }
+
+    pub fn get_frozen_accounts(
+        &self,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<AccountId> {
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+        self.frozen_accounts
+            .iter()
+            .skip(start as usize)
+            .take(limit.unwrap_or(self.frozen_accounts.len()) as usize)
+            .collect()
+    }
+}
+
+impl Contract {
+    pub(crate) fn assert_not_frozen(&self, account_id: &AccountId) {
+        require!(
+            !self.frozen_accounts.contains(account_id),
+            format!("The account {} is frozen", account_id)
+        );
+    }
+}
@@ -0,0 +1,248 @@
+//! NEP-141 core, resolver and NEP-145 storage methods.
+//!
+//! They delegate to the embedded `FungibleToken`, but every balance change goes through
+//! `Contract::internal_before_transfer` first so the contract extensions stay in sync with it.
+use crate::*;
+use near_contract_standards::fungible_token::core::FungibleTokenCore;
+use near_contract_standards::fungible_token::events::FtBurn;
+use near_contract_standards::fungible_token::receiver::ext_ft_receiver;
+use near_contract_standards::storage_management::{
+    StorageBalance, StorageBalanceBounds, StorageManagement,
+};
+use near_sdk::{Gas, PromiseResult};
+
+const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas(5_000_000_000_000);
+const GAS_FOR_FT_TRANSFER_CALL: Gas = Gas(25_000_000_000_000 + GAS_FOR_RESOLVE_TRANSFER.0);
+
+impl Contract {
+    /// Runs before any balance changes. `sender_id` is `None` when tokens are minted and
+    /// `receiver_id` is `None` when tokens are burned.
+    pub(crate) fn internal_before_transfer(
+        &mut self,
+        sender_id: Option<&AccountId>,
+        receiver_id: Option<&AccountId>,
+        amount: Balance,
+    ) {
+        if let Some(sender_id) = sender_id {
+            self.internal_assert_unlocked(sender_id, amount);
+        }
+        self.internal_before_refund(sender_id, receiver_id, amount);
+    }
+
+    /// Same as `internal_before_transfer` without the lockup check, which must not block
+    /// `ft_resolve_transfer` from returning tokens to their sender.
+    pub(crate) fn internal_before_refund(
+        &mut self,
+        sender_id: Option<&AccountId>,
+        receiver_id: Option<&AccountId>,
+        amount: Balance,
+    ) {
+        for account_id in sender_id.into_iter().chain(receiver_id) {
+            self.assert_not_frozen(account_id);
+        }
+        self.internal_sync_balance_change(sender_id, receiver_id, amount);
+    }
+
+    /// Brings the snapshots and the delegated votes up to date before a balance change, without
+    /// any check.
+    fn internal_sync_balance_change(
+        &mut self,
+        sender_id: Option<&AccountId>,
+        receiver_id: Option<&AccountId>,
+        amount: Balance,
+    ) {
+        for account_id in sender_id.into_iter().chain(receiver_id) {
+            self.internal_update_account_snapshot(account_id);
+        }
+        if sender_id.is_none() || receiver_id.is_none() {
+            self.internal_update_total_supply_snapshot();
+        }
+        self.internal_move_delegate_votes(sender_id, receiver_id, amount);
+    }
+
+    pub(crate) fn internal_transfer(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+        memo: Option<String>,
+    ) {
+        self.internal_before_transfer(Some(sender_id), Some(receiver_id), amount);
+        self.token.internal_transfer(sender_id, receiver_id, amount, memo);
+    }
+
+    /// Same as `internal_transfer`, without logging an `FtTransfer` event.
+    pub(crate) fn internal_transfer_unlogged(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+    ) {
+        require!(sender_id != receiver_id, "Sender and receiver should be different");
+        require!(amount > 0, "The amount should be a positive number");
+        self.internal_before_transfer(Some(sender_id), Some(receiver_id), amount);
+        self.token.internal_withdraw(sender_id, amount);
+        self.token.internal_deposit(receiver_id, amount);
+    }
+}
+
+#[near_bindgen]
+impl FungibleTokenCore for Contract {
+    #[payable]
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>) {
+        assert_one_yocto();
+        self.assert_not_paused(PausableOperation::Transfer);
+        let sender_id = env::predecessor_account_id();
+        self.internal_transfer_with_fee(&sender_id, &receiver_id, amount.into(), memo);
+    }
+
+    #[payable]
+    fn ft_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        assert_one_yocto();
+        self.assert_not_paused(PausableOperation::TransferCall);
+        require!(env::prepaid_gas() > GAS_FOR_FT_TRANSFER_CALL, "More gas is required");
+        let sender_id = env::predecessor_account_id();
+        let fee = self.internal_transfer_with_fee(&sender_id, &receiver_id, amount.into(), memo);
+        let amount = U128(amount.0 - fee.as_ref().map_or(0, |(_, fee)| *fee));
+        let receiver_gas = env::prepaid_gas()
+            .0
+            .checked_sub(GAS_FOR_FT_TRANSFER_CALL.0)
+            .unwrap_or_else(|| env::panic_str("Prepaid gas overflow"));
+        // Initiating receiver's call and the callback
+        ext_ft_receiver::ext(receiver_id.clone())
+            .with_static_gas(receiver_gas.into())
+            .ft_on_transfer(sender_id.clone(), amount, msg)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_TRANSFER)
+                    .ft_resolve_transfer(
+                        sender_id,
+                        receiver_id,
+                        amount,
+                        fee.map(|(treasury_id, fee)| (treasury_id, U128(fee))),
+                    ),
+            )
+            .into()
+    }
+
+    fn ft_total_supply(&self) -> U128 {
+        self.token.ft_total_supply()
+    }
+
+    fn ft_balance_of(&self, account_id: AccountId) -> U128 {
+        self.token.ft_balance_of(account_id)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Refunds the unused part of an `ft_transfer_call`, the same way
+    /// `FungibleToken::internal_ft_resolve_transfer` does, along with its share of the `fee` the
+    /// sender paid to the treasury. If the sender unregistered in the meantime, or the sender or
+    /// the receiver was frozen, the refund is burned instead so the receiver doesn't keep tokens
+    /// it didn't use.
+    #[private]
+    pub fn ft_resolve_transfer(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        fee: Option<(AccountId, U128)>,
+    ) -> U128 {
+        let amount: Balance = amount.into();
+
+        // Get the unused amount from the `ft_on_transfer` call result.
+        let unused_amount = match env::promise_result(0) {
+            PromiseResult::NotReady => env::abort(),
+            PromiseResult::Successful(value) => {
+                if let Ok(unused_amount) = near_sdk::serde_json::from_slice::<U128>(&value) {
+                    std::cmp::min(amount, unused_amount.0)
+                } else {
+                    amount
+                }
+            }
+            PromiseResult::Failed => amount,
+        };
+
+        let receiver_balance = self.token.accounts.get(&receiver_id).unwrap_or(0);
+        let refund_amount = std::cmp::min(receiver_balance, unused_amount);
+        if refund_amount == 0 {
+            return amount.into();
+        }
+        let frozen = self.frozen_accounts.contains(&sender_id)
+            || self.frozen_accounts.contains(&receiver_id);
+        if !frozen && self.token.accounts.contains_key(&sender_id) {
+            self.internal_before_refund(Some(&receiver_id), Some(&sender_id), refund_amount);
+            self.token.internal_transfer(
+                &receiver_id,
+                &sender_id,
+                refund_amount,
+                Some("refund".into()),
+            );
+            if let Some((treasury_id, fee)) = fee {
+                self.internal_refund_fee(&sender_id, &treasury_id, fee.0, amount, refund_amount);
+            }
+            (amount - refund_amount).into()
+        } else {
+            if frozen {
+                log!("The refund of {} is blocked by a frozen account", refund_amount);
+            } else {
+                log!("The account of the sender was deleted");
+            }
+            self.internal_sync_balance_change(Some(&receiver_id), None, refund_amount);
+            self.token.internal_withdraw(&receiver_id, refund_amount);
+            FtBurn { owner_id: &receiver_id, amount: &U128(refund_amount), memo: Some("refund") }
+                .emit();
+            self.on_tokens_burned(sender_id, refund_amount);
+            amount.into()
+        }
+    }
+}
+
+#[near_bindgen]
+impl StorageManagement for Contract {
+    #[payable]
+    fn storage_deposit(
+        &mut self,
+        account_id: Option<AccountId>,
+        registration_only: Option<bool>,
+    ) -> StorageBalance {
+        self.token.storage_deposit(account_id, registration_only)
+    }
+
+    #[payable]
+    fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
+        self.token.storage_withdraw(amount)
+    }
+
+    #[payable]
+    fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+        self.assert_not_paused(PausableOperation::StorageUnregister);
+        let account_id = env::predecessor_account_id();
+        self.assert_not_treasury(&account_id);
+        let balance = self.token.accounts.get(&account_id).unwrap_or(0);
+        if balance > 0 && force.unwrap_or(false) {
+            self.internal_before_transfer(Some(&account_id), None, balance);
+        }
+        if let Some((account_id, balance)) = self.token.internal_storage_unregister(force) {
+            self.on_account_closed(account_id, balance);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        self.token.storage_balance_bounds()
+    }
+
+    fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+        self.token.storage_balance_of(account_id)
+    }
+}
@@ -19,16 +19,96 @@ use near_contract_standards::fungible_token::metadata::{
     FungibleTokenMetadata, FungibleTokenMetadataProvider, FT_METADATA_SPEC,
 };
 use near_contract_standards::fungible_token::FungibleToken;
+use near_contract_standards::storage_management::StorageManagement;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::LazyOption;
+use near_sdk::collections::{LazyOption, LookupMap, LookupSet, UnorderedSet, Vector};
 use near_sdk::json_types::U128;
-use near_sdk::{env, log, near_bindgen, AccountId, Balance, PanicOnDefault, PromiseOrValue};
+use near_sdk::{
+    assert_one_yocto, env, log, near_bindgen, require, AccountId, Balance, PanicOnDefault, Promise,
+    PromiseOrValue, PublicKey, StorageUsage,
+};
+
+use crate::airdrop::AirdropCampaign;
+use crate::emission::EmissionSchedule;
+use crate::fee::TransferFee;
+use crate::lockup::Lockup;
+use crate::pause::PausableOperation;
+use crate::snapshot::Snapshot;
+use crate::votes::Checkpoint;
+
+mod airdrop;
+mod allowance;
+mod authorization;
+mod batch;
+mod emission;
+mod events;
+mod fee;
+mod freeze;
+mod ft_core;
+mod lockup;
+mod math;
+mod metadata;
+mod mint;
+mod pause;
+mod snapshot;
+mod upgrade;
+mod votes;
+mod wrap;
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct Contract {
     token: FungibleToken,
     metadata: LazyOption<FungibleTokenMetadata>,
+    /// Account allowed to manage the minter registry.
+    owner_id: AccountId,
+    /// Accounts allowed to call `ft_mint` and `ft_burn`.
+    minters: UnorderedSet<AccountId>,
+    /// Account allowed to pause and unpause operations next to the owner.
+    guardian_id: Option<AccountId>,
+    /// Operations that are currently halted.
+    paused_operations: Vec<PausableOperation>,
+    /// (owner, spender) -> amount the spender can still transfer on behalf of the owner.
+    allowances: LookupMap<(AccountId, AccountId), Balance>,
+    /// Id of the latest balance snapshot, 0 before the first one is taken.
+    current_snapshot_id: u64,
+    /// (account, index) -> balance recorded for a past snapshot, ordered by snapshot id.
+    account_snapshots: LookupMap<(AccountId, u32), Snapshot>,
+    /// Account -> number of balances recorded in `account_snapshots`.
+    account_snapshot_counts: LookupMap<AccountId, u32>,
+    /// Total supply recorded for past snapshots, ordered by snapshot id.
+    total_supply_snapshots: Vector<Snapshot>,
+    /// Account -> account it delegates its voting power to.
+    delegations: LookupMap<AccountId, AccountId>,
+    /// (delegate, index) -> voting power checkpoint, ordered by block height.
+    vote_checkpoints: LookupMap<(AccountId, u32), Checkpoint>,
+    /// Delegate -> number of checkpoints in `vote_checkpoints`.
+    vote_checkpoint_counts: LookupMap<AccountId, u32>,
+    /// Accounts that can neither send nor receive tokens.
+    frozen_accounts: UnorderedSet<AccountId>,
+    /// Fee taken on transfers, if any.
+    transfer_fee: Option<TransferFee>,
+    /// Accounts whose incoming and outgoing transfers are free.
+    fee_exempt_accounts: UnorderedSet<AccountId>,
+    /// Upper bound for the total supply, if any.
+    max_supply: Option<Balance>,
+    emission_schedule: Option<EmissionSchedule>,
+    /// Number of epochs of the emission schedule that were already minted.
+    released_epochs: u32,
+    /// Cumulative amount minted by the emission schedule.
+    emitted_amount: Balance,
+    /// Account -> tokens locked in its balance.
+    lockups: LookupMap<AccountId, Vec<Lockup>>,
+    /// Whether the token is wrapped NEAR, see `new_wrapped_near`.
+    wrapped_near: bool,
+    /// Account -> key that signs its transfer authorizations.
+    authorization_keys: LookupMap<AccountId, PublicKey>,
+    /// Account -> nonce its next transfer authorization must use.
+    authorization_nonces: LookupMap<AccountId, u64>,
+    /// Airdrop campaigns, indexed by campaign id.
+    airdrops: Vector<AirdropCampaign>,
+    /// (campaign id, account) pairs that already claimed their airdrop.
+    airdrop_claims: LookupSet<(u64, AccountId)>,
 }
 
 const DATA_IMAGE_SVG_NEAR_ICON: &str = "data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 288 288'%3E%3Cg id='l' data-name='l'%3E%3Cpath d='M187.58,79.81l-30.1,44.69a3.2,3.2,0,0,0,4.75,4.2L191.86,103a1.2,1.2,0,0,1,2,.91v80.46a1.2,1.2,0,0,1-2.12.77L102.18,77.93A15.35,15.35,0,0,0,90.47,72.5H87.34A15.34,15.34,0,0,0,72,87.84V201.16A15.34,15.34,0,0,0,87.34,216.5h0a15.35,15.35,0,0,0,13.08-7.31l30.1-44.69a3.2,3.2,0,0,0-4.75-4.2L96.14,186a1.2,1.2,0,0,1-2-.91V104.61a1.2,1.2,0,0,1,2.12-.77l89.55,107.23a15.35,15.35,0,0,0,11.71,5.43h3.13A15.34,15.34,0,0,0,216,201.16V87.84A15.34,15.34,0,0,0,200.66,72.5h0A15.35,15.35,0,0,0,187.58,79.81Z'/%3E%3C/g%3E%3C/svg%3E";
@@ -51,31 +131,41 @@ impl Contract {
                 reference_hash: None,
                 decimals: 24,
             },
+            None,
         )
     }
 
     /// Initializes the contract with the given total supply owned by the given `owner_id` with
-    /// the given fungible token metadata.
+    /// the given fungible token metadata. If `max_supply` is given, minting can never take the
+    /// total supply above it.
     #[init]
     pub fn new(
         owner_id: AccountId,
         total_supply: U128,
         metadata: FungibleTokenMetadata,
+        max_supply: Option<U128>,
     ) -> Self {
         assert!(!env::state_exists(), "Already initialized");
         metadata.assert_valid();
-        let mut this = Self {
-            token: FungibleToken::new(b"a".to_vec()),
-            metadata: LazyOption::new(b"m".to_vec(), Some(&metadata)),
-        };
+        if let Some(max_supply) = max_supply {
+            require!(total_supply.0 <= max_supply.0, "The total supply exceeds the maximum supply");
+        }
+        let mut this = Self::from_token(
+            FungibleToken::new(b"a".to_vec()),
+            LazyOption::new(b"m".to_vec(), Some(&metadata)),
+            owner_id.clone(),
+        );
+        this.max_supply = max_supply.map(|max_supply| max_supply.0);
         this.token.internal_register_account(&owner_id);
-        this.token.internal_deposit(&owner_id, total_supply.into());
-        near_contract_standards::fungible_token::events::FtMint {
-            owner_id: &owner_id,
-            amount: &total_supply,
-            memo: Some("Initial tokens supply is minted"),
+        if total_supply.0 > 0 {
+            this.token.internal_deposit(&owner_id, total_supply.into());
+            near_contract_standards::fungible_token::events::FtMint {
+                owner_id: &owner_id,
+                amount: &total_supply,
+                memo: Some("Initial tokens supply is minted"),
+            }
+            .emit();
         }
-        .emit();
         this
     }
 
@@ -86,20 +176,91 @@ impl Contract {
     fn on_tokens_burned(&mut self, account_id: AccountId, amount: Balance) {
         log!("Account @{} burned {}", account_id, amount);
     }
+}
+
+impl Contract {
+    /// Builds the state around `token` and `metadata` with every extension empty or disabled.
+    pub(crate) fn from_token(
+        token: FungibleToken,
+        metadata: LazyOption<FungibleTokenMetadata>,
+        owner_id: AccountId,
+    ) -> Self {
+        Self {
+            token,
+            metadata,
+            owner_id,
+            minters: UnorderedSet::new(b"r".to_vec()),
+            guardian_id: None,
+            paused_operations: Vec::new(),
+            allowances: LookupMap::new(b"l".to_vec()),
+            current_snapshot_id: 0,
+            account_snapshots: LookupMap::new(b"s".to_vec()),
+            account_snapshot_counts: LookupMap::new(b"S".to_vec()),
+            total_supply_snapshots: Vector::new(b"t".to_vec()),
+            delegations: LookupMap::new(b"d".to_vec()),
+            vote_checkpoints: LookupMap::new(b"v".to_vec()),
+            vote_checkpoint_counts: LookupMap::new(b"V".to_vec()),
+            frozen_accounts: UnorderedSet::new(b"f".to_vec()),
+            transfer_fee: None,
+            fee_exempt_accounts: UnorderedSet::new(b"e".to_vec()),
+            max_supply: None,
+            emission_schedule: None,
+            released_epochs: 0,
+            emitted_amount: 0,
+            lockups: LookupMap::new(b"k".to_vec()),
+            wrapped_near: false,
+            authorization_keys: LookupMap::new(b"z".to_vec()),
+            authorization_nonces: LookupMap::new(b"n".to_vec()),
+            airdrops: Vector::new(b"i".to_vec()),
+            airdrop_claims: LookupSet::new(b"c".to_vec()),
+        }
+    }
 
-    #[private]
-    pub fn set_icon(&mut self, icon: String) {
-        // Get the current metadata (if it exists)
-        if let Some(mut metadata) = self.metadata.get() {
-            // Change the icon
-            metadata.icon = Some(icon);
-            self.metadata.set(&metadata);
+    pub(crate) fn assert_owner(&self) {
+        require!(
+            env::predecessor_account_id() == self.owner_id,
+            "Only the owner can call this method"
+        );
+    }
+}
+
+/// Returns the index of the first element for which `pred` is false, with `pred` true for every
+/// element before it and false for every element after, like `slice::partition_point`.
+pub(crate) fn partition_point(len: u64, pred: impl Fn(u64) -> bool) -> u64 {
+    let (mut low, mut high) = (0, len);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if pred(mid) {
+            low = mid + 1;
+        } else {
+            high = mid;
         }
     }
+    low
 }
 
-near_contract_standards::impl_fungible_token_core!(Contract, token, on_tokens_burned);
-near_contract_standards::impl_fungible_token_storage!(Contract, token, on_account_closed);
+/// Charges the caller for the storage added since `initial_storage_usage` and refunds the rest of
+/// the attached deposit, plus the cost of any storage that was released.
+pub(crate) fn refund_deposit(initial_storage_usage: StorageUsage) {
+    let current_storage_usage = env::storage_usage();
+    let attached_deposit = env::attached_deposit();
+    let refund = if current_storage_usage >= initial_storage_usage {
+        let required_deposit =
+            Balance::from(current_storage_usage - initial_storage_usage) * env::storage_byte_cost();
+        require!(
+            attached_deposit >= required_deposit,
+            format!("Must attach {} yoctoNEAR to cover storage", required_deposit)
+        );
+        attached_deposit - required_deposit
+    } else {
+        attached_deposit
+            + Balance::from(initial_storage_usage - current_storage_usage)
+                * env::storage_byte_cost()
+    };
+    if refund > 0 {
+        Promise::new(env::predecessor_account_id()).transfer(refund);
+    }
+}
 
 #[near_bindgen]
 impl FungibleTokenMetadataProvider for Contract {
@@ -110,13 +271,33 @@ impl FungibleTokenMetadataProvider for Contract {
 
 #[cfg(all(test, not(target_arch = "wasm32")))]
 mod tests {
-    use near_sdk::test_utils::{accounts, VMContextBuilder};
-    use near_sdk::MockedBlockchain;
-    use near_sdk::{testing_env, Balance};
+    use near_contract_standards::fungible_token::core::FungibleTokenCore;
+    use near_contract_standards::storage_management::StorageManagement;
+    use near_sdk::json_types::U64;
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, Balance, Gas, PromiseResult, RuntimeFeesConfig, VMConfig};
 
     use super::*;
+    use crate::authorization::TransferAuthorization;
+    use crate::emission::EmissionPool;
+    use crate::upgrade::ContractV1;
+    use near_sdk::json_types::Base64VecU8;
+    use std::convert::TryInto;
 
     const TOTAL_SUPPLY: Balance = 1_000_000_000_000_000;
+    const EXTRA_STORAGE_DEPOSIT: Balance = 10_000_000_000_000_000_000_000;
+
+    fn contract_metadata() -> FungibleTokenMetadata {
+        FungibleTokenMetadata {
+            spec: FT_METADATA_SPEC.to_string(),
+            name: "Realities".to_string(),
+            symbol: "RLTS".to_string(),
+            icon: None,
+            reference: None,
+            reference_hash: None,
+            decimals: 8,
+        }
+    }
 
     fn get_context(predecessor_account_id: AccountId) -> VMContextBuilder {
         let mut builder = VMContextBuilder::new();
@@ -131,7 +312,7 @@ mod tests {
     fn test_new() {
         let mut context = get_context(accounts(1));
         testing_env!(context.build());
-        let contract = Contract::new_default_meta(accounts(1).into(), TOTAL_SUPPLY.into());
+        let contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
         testing_env!(context.is_view(true).build());
         assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY);
         assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY);
@@ -149,7 +330,7 @@ mod tests {
     fn test_transfer() {
         let mut context = get_context(accounts(2));
         testing_env!(context.build());
-        let mut contract = Contract::new_default_meta(accounts(2).into(), TOTAL_SUPPLY.into());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
         testing_env!(context
             .storage_usage(env::storage_usage())
             .attached_deposit(contract.storage_balance_bounds().min.into())
@@ -175,4 +356,1232 @@ mod tests {
         assert_eq!(contract.ft_balance_of(accounts(2)).0, (TOTAL_SUPPLY - transfer_amount));
         assert_eq!(contract.ft_balance_of(accounts(1)).0, transfer_amount);
     }
+
+    #[test]
+    fn test_approve_and_transfer_from() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.storage_deposit(None, None);
+
+        // accounts(2) allows accounts(3) to spend a third of the supply
+        let allowance = TOTAL_SUPPLY / 3;
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(EXTRA_STORAGE_DEPOSIT)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.ft_approve(accounts(3), allowance.into());
+        assert_eq!(contract.ft_allowance(accounts(2), accounts(3)).0, allowance);
+
+        // accounts(3) moves part of it to accounts(1)
+        let transfer_amount = allowance / 2;
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(3))
+            .build());
+        contract.ft_transfer_from(accounts(2), accounts(1), transfer_amount.into(), None);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .account_balance(env::account_balance())
+            .is_view(true)
+            .attached_deposit(0)
+            .build());
+        assert_eq!(contract.ft_allowance(accounts(2), accounts(3)).0, allowance - transfer_amount);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY - transfer_amount);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, transfer_amount);
+    }
+
+    #[test]
+    #[should_panic(expected = "The amount exceeds the allowance")]
+    fn test_transfer_from_exceeds_allowance() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(EXTRA_STORAGE_DEPOSIT)
+            .build());
+        contract.ft_approve(accounts(3), (TOTAL_SUPPLY / 3).into());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(3))
+            .build());
+        contract.ft_transfer_from(accounts(2), accounts(3), (TOTAL_SUPPLY / 2).into(), None);
+    }
+
+    #[test]
+    fn test_mint_and_burn() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.storage_deposit(None, None);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.add_minter(accounts(1));
+        assert!(contract.is_minter(accounts(1)));
+        assert_eq!(contract.get_minters(None, None), vec![accounts(1)]);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(1))
+            .build());
+        let mint_amount = TOTAL_SUPPLY / 2;
+        contract.ft_mint(accounts(1), mint_amount.into(), None);
+        let burn_amount = TOTAL_SUPPLY / 5;
+        contract.ft_burn(burn_amount.into(), None);
+
+        testing_env!(context.is_view(true).attached_deposit(0).build());
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, mint_amount - burn_amount);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + mint_amount - burn_amount);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only minters can call this method")]
+    fn test_mint_not_minter() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_mint(accounts(2), TOTAL_SUPPLY.into(), None);
+    }
+
+    #[test]
+    fn test_guardian_pause_and_unpause() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context.attached_deposit(1).build());
+        contract.set_guardian(Some(accounts(3)));
+
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.pause(Some(vec![PausableOperation::Transfer]));
+        assert_eq!(contract.get_paused_operations(), vec![PausableOperation::Transfer]);
+        contract.pause(None);
+        assert_eq!(contract.get_paused_operations().len(), 3);
+        contract.unpause(Some(vec![PausableOperation::TransferCall]));
+        assert_eq!(
+            contract.get_paused_operations(),
+            vec![PausableOperation::Transfer, PausableOperation::StorageUnregister]
+        );
+        contract.unpause(None);
+        assert!(contract.get_paused_operations().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "The operation Transfer is paused")]
+    fn test_transfer_when_paused() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context.attached_deposit(1).build());
+        contract.pause(None);
+        contract.ft_transfer(accounts(1), (TOTAL_SUPPLY / 3).into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner or the guardian can call this method")]
+    fn test_pause_not_guardian() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(3)).build());
+        contract.pause(None);
+    }
+
+    #[test]
+    fn test_balance_snapshots() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.storage_deposit(None, None);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.add_minter(accounts(2));
+        let first_snapshot = contract.ft_snapshot();
+        let transfer_amount = TOTAL_SUPPLY / 4;
+        contract.ft_transfer(accounts(1), transfer_amount.into(), None);
+        contract.ft_transfer(accounts(1), transfer_amount.into(), None);
+        let second_snapshot = contract.ft_snapshot();
+        contract.ft_mint(accounts(1), TOTAL_SUPPLY.into(), None);
+        let third_snapshot = contract.ft_snapshot();
+
+        testing_env!(context.is_view(true).attached_deposit(0).build());
+        assert_eq!(contract.ft_balance_of_at(accounts(2), first_snapshot).0, TOTAL_SUPPLY);
+        assert_eq!(contract.ft_balance_of_at(accounts(1), first_snapshot).0, 0);
+        assert_eq!(contract.ft_total_supply_at(first_snapshot).0, TOTAL_SUPPLY);
+        assert_eq!(contract.ft_balance_of_at(accounts(1), second_snapshot).0, 2 * transfer_amount);
+        assert_eq!(contract.ft_total_supply_at(second_snapshot).0, TOTAL_SUPPLY);
+        assert_eq!(
+            contract.ft_balance_of_at(accounts(1), third_snapshot).0,
+            TOTAL_SUPPLY + 2 * transfer_amount
+        );
+        assert_eq!(contract.ft_total_supply_at(third_snapshot).0, 2 * TOTAL_SUPPLY);
+    }
+
+    #[test]
+    fn test_balance_snapshots_history() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.storage_deposit(None, None);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        for _ in 0..10 {
+            contract.ft_snapshot();
+            contract.ft_transfer(accounts(1), 1.into(), None);
+        }
+
+        testing_env!(context.is_view(true).attached_deposit(0).build());
+        for snapshot_id in 1..=10 {
+            assert_eq!(
+                contract.ft_balance_of_at(accounts(1), snapshot_id).0,
+                Balance::from(snapshot_id as u32) - 1
+            );
+            assert_eq!(
+                contract.ft_balance_of_at(accounts(2), snapshot_id).0,
+                TOTAL_SUPPLY - Balance::from(snapshot_id as u32) + 1
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "The snapshot doesn't exist yet")]
+    fn test_balance_of_at_future_snapshot() {
+        let context = get_context(accounts(2));
+        testing_env!(context.build());
+        let contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        contract.ft_balance_of_at(accounts(2), 1);
+    }
+
+    #[test]
+    fn test_delegate_votes() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.block_index(10).build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.storage_deposit(None, None);
+
+        // accounts(2) delegates to accounts(3), accounts(1) votes with its own balance
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(EXTRA_STORAGE_DEPOSIT)
+            .build());
+        contract.delegate(accounts(1));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(EXTRA_STORAGE_DEPOSIT)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.delegate(accounts(3));
+        assert_eq!(contract.delegates(accounts(2)), Some(accounts(3)));
+        assert_eq!(contract.get_votes(accounts(3)).0, TOTAL_SUPPLY);
+
+        let transfer_amount = TOTAL_SUPPLY / 4;
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .block_index(20)
+            .build());
+        contract.ft_transfer(accounts(1), transfer_amount.into(), None);
+
+        testing_env!(context.is_view(true).attached_deposit(0).block_index(30).build());
+        assert_eq!(contract.get_votes(accounts(3)).0, TOTAL_SUPPLY - transfer_amount);
+        assert_eq!(contract.get_votes(accounts(1)).0, transfer_amount);
+        assert_eq!(contract.get_past_votes(accounts(3), 15).0, TOTAL_SUPPLY);
+        assert_eq!(contract.get_past_votes(accounts(1), 15).0, 0);
+        assert_eq!(contract.get_past_votes(accounts(1), 20).0, transfer_amount);
+        assert_eq!(contract.get_past_votes(accounts(3), 5).0, 0);
+    }
+
+    #[test]
+    fn test_past_votes_history() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.block_index(10).build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.storage_deposit(None, None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(EXTRA_STORAGE_DEPOSIT)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.delegate(accounts(2));
+
+        // two transfers per block, only the last votes of a block are checkpointed
+        for block in 1..=10 {
+            testing_env!(context
+                .storage_usage(env::storage_usage())
+                .attached_deposit(1)
+                .block_index(block * 100)
+                .build());
+            contract.ft_transfer(accounts(1), 1.into(), None);
+            contract.ft_transfer(accounts(1), 1.into(), None);
+        }
+
+        testing_env!(context.is_view(true).attached_deposit(0).block_index(2_000).build());
+        assert_eq!(contract.get_past_votes(accounts(2), 50).0, TOTAL_SUPPLY);
+        for block in 1..=10 {
+            let votes = TOTAL_SUPPLY - 2 * Balance::from(block as u32);
+            assert_eq!(contract.get_past_votes(accounts(2), block * 100).0, votes);
+            assert_eq!(contract.get_past_votes(accounts(2), block * 100 + 99).0, votes);
+        }
+        assert_eq!(contract.get_votes(accounts(2)).0, TOTAL_SUPPLY - 20);
+    }
+
+    #[test]
+    fn test_freeze_and_unfreeze() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context.attached_deposit(1).build());
+        contract.freeze_account(accounts(1), Some("stolen funds".to_string()));
+        contract.freeze_account(accounts(3), None);
+        assert!(contract.is_frozen(accounts(1)));
+        assert_eq!(contract.get_frozen_accounts(Some(U128(1)), None), vec![accounts(3)]);
+        contract.unfreeze_account(accounts(1), None);
+        assert!(!contract.is_frozen(accounts(1)));
+        assert_eq!(contract.get_frozen_accounts(None, None), vec![accounts(3)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "The account charlie is frozen")]
+    fn test_transfer_to_frozen_account() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context.attached_deposit(1).build());
+        contract.freeze_account(accounts(2), None);
+        contract.ft_transfer(accounts(1), (TOTAL_SUPPLY / 3).into(), None);
+    }
+
+    #[test]
+    fn test_transfer_call_refund_frozen() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.storage_deposit(None, None);
+        let transfer_amount = TOTAL_SUPPLY / 4;
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .prepaid_gas(near_sdk::Gas(300_000_000_000_000))
+            .build());
+        contract.ft_transfer_call(accounts(1), transfer_amount.into(), None, "".to_string());
+
+        // the receiver is frozen before it reports half of the tokens as unused
+        contract.freeze_account(accounts(1), None);
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).attached_deposit(0).build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(
+                near_sdk::serde_json::to_vec(&U128(transfer_amount / 2)).unwrap()
+            )],
+        );
+        let used_amount =
+            contract.ft_resolve_transfer(accounts(2), accounts(1), transfer_amount.into(), None);
+        assert_eq!(used_amount.0, transfer_amount);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, transfer_amount / 2);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY - transfer_amount);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY - transfer_amount / 2);
+    }
+
+    fn setup_transfer_fee() -> (VMContextBuilder, Contract) {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        for account_id in [accounts(1), accounts(3), accounts(4)] {
+            testing_env!(context
+                .storage_usage(env::storage_usage())
+                .attached_deposit(contract.storage_balance_bounds().min.into())
+                .predecessor_account_id(account_id)
+                .build());
+            contract.storage_deposit(None, None);
+        }
+        // 1% of every transfer goes to accounts(3), transfers to accounts(4) are free
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.set_transfer_fee(Some(TransferFee { fee_bps: 100, treasury_id: accounts(3) }));
+        contract.add_fee_exemption(accounts(4));
+        (context, contract)
+    }
+
+    #[test]
+    fn test_transfer_fee() {
+        let (mut context, mut contract) = setup_transfer_fee();
+        let transfer_amount = TOTAL_SUPPLY / 10;
+        contract.ft_transfer(accounts(1), transfer_amount.into(), None);
+        contract.ft_transfer(accounts(4), transfer_amount.into(), None);
+
+        testing_env!(context.is_view(true).attached_deposit(0).build());
+        let fee = transfer_amount / 100;
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, transfer_amount - fee);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, fee);
+        assert_eq!(contract.ft_balance_of(accounts(4)).0, transfer_amount);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY - 2 * transfer_amount);
+    }
+
+    #[test]
+    #[should_panic(expected = "The treasury can't unregister while the transfer fee is on")]
+    fn test_treasury_unregister() {
+        let (mut context, mut contract) = setup_transfer_fee();
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.storage_unregister(Some(true));
+    }
+
+    #[test]
+    fn test_transfer_call_refund_with_fee() {
+        let (mut context, mut contract) = setup_transfer_fee();
+        let transfer_amount = TOTAL_SUPPLY / 10;
+        let fee = transfer_amount / 100;
+        testing_env!(context.prepaid_gas(near_sdk::Gas(300_000_000_000_000)).build());
+        contract.ft_transfer_call(accounts(1), transfer_amount.into(), None, "".to_string());
+
+        // the receiver reports that the whole amount it was told about is unused
+        let unused_amount = transfer_amount - fee;
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).attached_deposit(0).build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(
+                near_sdk::serde_json::to_vec(&U128(unused_amount)).unwrap()
+            )],
+        );
+        let used_amount = contract.ft_resolve_transfer(
+            accounts(2),
+            accounts(1),
+            unused_amount.into(),
+            Some((accounts(3), fee.into())),
+        );
+        assert_eq!(used_amount.0, 0);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 0);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 0);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY);
+    }
+
+    /// Schedules four epochs of 0.1 supply to accounts(1) and 0.2 supply to accounts(2), owned by
+    /// accounts(2), under a maximum supply of `max_supply`.
+    fn setup_emission(max_supply: Balance) -> (VMContextBuilder, Contract) {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new(
+            accounts(2),
+            TOTAL_SUPPLY.into(),
+            contract_metadata(),
+            Some(max_supply.into()),
+        );
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.storage_deposit(None, None);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.set_emission_schedule(EmissionSchedule {
+            start: U64(1_000),
+            epoch_duration: U64(100),
+            epochs: 4,
+            pools: vec![
+                EmissionPool {
+                    account_id: accounts(1),
+                    amount_per_epoch: (TOTAL_SUPPLY / 10).into(),
+                },
+                EmissionPool {
+                    account_id: accounts(2),
+                    amount_per_epoch: (TOTAL_SUPPLY / 5).into(),
+                },
+            ],
+        });
+
+        (context, contract)
+    }
+
+    #[test]
+    fn test_release_emission() {
+        let (mut context, mut contract) = setup_emission(3 * TOTAL_SUPPLY);
+        testing_env!(context.attached_deposit(0).block_timestamp(999).build());
+        assert_eq!(contract.release_emission().0, 0);
+        testing_env!(context.block_timestamp(1_150).build());
+        assert_eq!(contract.release_emission().0, 2 * (TOTAL_SUPPLY / 10 + TOTAL_SUPPLY / 5));
+        assert_eq!(contract.release_emission().0, 0);
+        testing_env!(context.block_timestamp(10_000).build());
+        contract.release_emission();
+
+        assert_eq!(contract.get_emitted_amount().0, 4 * (TOTAL_SUPPLY / 10 + TOTAL_SUPPLY / 5));
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 4 * (TOTAL_SUPPLY / 10));
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 4 * (TOTAL_SUPPLY * 3 / 10));
+    }
+
+    #[test]
+    fn test_release_emission_up_to_max_supply() {
+        let (mut context, mut contract) = setup_emission(3 * TOTAL_SUPPLY);
+        contract.set_max_supply((TOTAL_SUPPLY + TOTAL_SUPPLY / 2).into());
+
+        testing_env!(context.attached_deposit(0).block_timestamp(10_000).build());
+        assert_eq!(contract.release_emission().0, TOTAL_SUPPLY / 2);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 4 * (TOTAL_SUPPLY / 10));
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + TOTAL_SUPPLY / 2);
+        assert_eq!(contract.release_emission().0, 0);
+    }
+
+    #[test]
+    fn test_release_emission_to_new_pool_account() {
+        let (mut context, mut contract) = setup_emission(3 * TOTAL_SUPPLY);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(3))
+            .build());
+        contract.storage_deposit(None, None);
+        testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(2)).build());
+        contract.freeze_account(accounts(1), None);
+        contract.set_emission_pool_account(0, accounts(3));
+
+        testing_env!(context.attached_deposit(0).block_timestamp(1_000).build());
+        assert_eq!(contract.release_emission().0, TOTAL_SUPPLY / 10 + TOTAL_SUPPLY / 5);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, TOTAL_SUPPLY / 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "The maximum supply can't be below the total supply")]
+    fn test_set_max_supply_below_total_supply() {
+        let (_, mut contract) = setup_emission(3 * TOTAL_SUPPLY);
+        contract.set_max_supply((TOTAL_SUPPLY - 1).into());
+    }
+
+    #[test]
+    #[should_panic(expected = "The amount exceeds the maximum supply")]
+    fn test_mint_above_max_supply() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new(
+            accounts(2),
+            TOTAL_SUPPLY.into(),
+            contract_metadata(),
+            Some((TOTAL_SUPPLY + 1).into()),
+        );
+        testing_env!(context.attached_deposit(1).build());
+        contract.add_minter(accounts(2));
+        contract.ft_mint(accounts(2), 2.into(), None);
+    }
+
+    #[test]
+    fn test_transfer_locked() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        for account_id in [accounts(1), accounts(3)] {
+            testing_env!(context
+                .storage_usage(env::storage_usage())
+                .attached_deposit(contract.storage_balance_bounds().min.into())
+                .predecessor_account_id(account_id)
+                .build());
+            contract.storage_deposit(None, None);
+        }
+
+        // 0.4 supply locked for 1_000ns with a 250ns cliff
+        let locked_amount = TOTAL_SUPPLY * 2 / 5;
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.ft_transfer_locked(
+            accounts(1),
+            Lockup {
+                amount: locked_amount.into(),
+                start: U64(1_000),
+                cliff_duration: U64(250),
+                duration: U64(1_000),
+            },
+            None,
+        );
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, locked_amount);
+
+        testing_env!(context.block_timestamp(1_200).build());
+        assert_eq!(contract.ft_unlocked_balance_of(accounts(1)).0, 0);
+        testing_env!(context.block_timestamp(1_500).predecessor_account_id(accounts(1)).build());
+        assert_eq!(contract.ft_unlocked_balance_of(accounts(1)).0, locked_amount / 2);
+        contract.ft_transfer(accounts(3), (locked_amount / 4).into(), None);
+        assert_eq!(contract.ft_unlocked_balance_of(accounts(1)).0, locked_amount / 4);
+        testing_env!(context.block_timestamp(2_000).build());
+        assert_eq!(contract.ft_unlocked_balance_of(accounts(1)).0, locked_amount * 3 / 4);
+        contract.ft_transfer(accounts(3), (locked_amount * 3 / 4).into(), None);
+        assert!(contract.ft_lockups_of(accounts(1)).is_empty());
+    }
+
+    #[test]
+    fn test_transfer_locked_large_amount() {
+        // 1M tokens with 24 decimals, locked for a year and a half
+        const NANOSECONDS_PER_DAY: u64 = 86_400_000_000_000;
+        let locked_amount: Balance = 1_000_000 * 10u128.pow(24);
+        let duration = 540 * NANOSECONDS_PER_DAY;
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), locked_amount.into());
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.storage_deposit(None, None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.ft_transfer_locked(
+            accounts(1),
+            Lockup {
+                amount: locked_amount.into(),
+                start: U64(0),
+                cliff_duration: U64(0),
+                duration: U64(duration),
+            },
+            None,
+        );
+
+        testing_env!(context.block_timestamp(duration / 3).build());
+        assert_eq!(contract.ft_unlocked_balance_of(accounts(1)).0, locked_amount / 3);
+        testing_env!(context.block_timestamp(duration - NANOSECONDS_PER_DAY).build());
+        assert_eq!(
+            contract.ft_unlocked_balance_of(accounts(1)).0,
+            998_148_148_148_148_148_148_148_148_148
+        );
+    }
+
+    #[test]
+    fn test_transfer_call_refund_locked() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        for account_id in [accounts(1), accounts(3)] {
+            testing_env!(context
+                .storage_usage(env::storage_usage())
+                .attached_deposit(contract.storage_balance_bounds().min.into())
+                .predecessor_account_id(account_id)
+                .build());
+            contract.storage_deposit(None, None);
+        }
+        let locked_amount = TOTAL_SUPPLY / 2;
+        let transfer_amount = TOTAL_SUPPLY / 4;
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .prepaid_gas(near_sdk::Gas(300_000_000_000_000))
+            .build());
+        contract.ft_transfer_locked(
+            accounts(1),
+            Lockup {
+                amount: locked_amount.into(),
+                start: U64(0),
+                cliff_duration: U64(500),
+                duration: U64(1_000),
+            },
+            None,
+        );
+        contract.ft_transfer_call(accounts(1), transfer_amount.into(), None, "".to_string());
+
+        // the receiver moves the tokens it got on, then reports them all as unused
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_transfer(accounts(3), transfer_amount.into(), None);
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).attached_deposit(0).build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(
+                near_sdk::serde_json::to_vec(&U128(transfer_amount)).unwrap()
+            )],
+        );
+        let used_amount =
+            contract.ft_resolve_transfer(accounts(2), accounts(1), transfer_amount.into(), None);
+        assert_eq!(used_amount.0, 0);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, locked_amount - transfer_amount);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY - locked_amount);
+    }
+
+    #[test]
+    #[should_panic(expected = "The operation Transfer is paused")]
+    fn test_transfer_locked_paused() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context.attached_deposit(1).build());
+        contract.pause(None);
+        contract.ft_transfer_locked(
+            accounts(1),
+            Lockup {
+                amount: 1.into(),
+                start: U64(0),
+                cliff_duration: U64(0),
+                duration: U64(1_000),
+            },
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "only has 0 unlocked tokens")]
+    fn test_transfer_locked_before_cliff() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.storage_deposit(None, None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.ft_transfer_locked(
+            accounts(1),
+            Lockup {
+                amount: TOTAL_SUPPLY.into(),
+                start: U64(0),
+                cliff_duration: U64(500),
+                duration: U64(1_000),
+            },
+            None,
+        );
+        testing_env!(context.block_timestamp(100).predecessor_account_id(accounts(1)).build());
+        contract.ft_transfer(accounts(2), 1.into(), None);
+    }
+
+    #[test]
+    fn test_update_metadata() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context.attached_deposit(1).build());
+        contract.set_name("Realities Token".to_string());
+        contract.set_icon(None);
+        contract.set_reference(
+            Some("https://example.com/rlts.json".to_string()),
+            Some(vec![0; 32].into()),
+        );
+        let metadata = contract.ft_metadata();
+        assert_eq!(metadata.name, "Realities Token");
+        assert_eq!(metadata.symbol, "EXAMPLE");
+        assert!(metadata.icon.is_none());
+        assert_eq!(metadata.reference_hash.unwrap().0, vec![0; 32]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Hash has to be 32 bytes")]
+    fn test_update_metadata_invalid_hash() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context.attached_deposit(1).build());
+        contract.set_reference(
+            Some("https://example.com/rlts.json".to_string()),
+            Some(vec![0; 16].into()),
+        );
+    }
+
+    fn wrapped_near_metadata() -> FungibleTokenMetadata {
+        FungibleTokenMetadata {
+            spec: FT_METADATA_SPEC.to_string(),
+            name: "Wrapped NEAR".to_string(),
+            symbol: "wNEAR".to_string(),
+            icon: None,
+            reference: None,
+            reference_hash: None,
+            decimals: 24,
+        }
+    }
+
+    #[test]
+    fn test_near_deposit_and_withdraw() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_wrapped_near(accounts(2), wrapped_near_metadata());
+        assert_eq!(contract.ft_total_supply().0, 0);
+
+        let storage_deposit: Balance = contract.storage_balance_bounds().min.into();
+        let amount: Balance = 5 * 10u128.pow(24);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(storage_deposit + amount)
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.near_deposit();
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, amount);
+        assert_eq!(contract.storage_balance_of(accounts(1)).unwrap().total.0, storage_deposit);
+
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.near_withdraw((amount / 5).into());
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, amount * 4 / 5);
+        assert_eq!(contract.ft_total_supply().0, amount * 4 / 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "The contract doesn't wrap NEAR")]
+    fn test_near_deposit_not_wrapped_near() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context.attached_deposit(EXTRA_STORAGE_DEPOSIT).build());
+        contract.near_deposit();
+    }
+
+    #[test]
+    #[should_panic(expected = "Wrapped NEAR can only be minted by depositing NEAR")]
+    fn test_mint_wrapped_near() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_wrapped_near(accounts(2), wrapped_near_metadata());
+        testing_env!(context.attached_deposit(1).build());
+        contract.add_minter(accounts(2));
+        contract.ft_mint(accounts(2), 1.into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Wrapped NEAR can only be minted by depositing NEAR")]
+    fn test_release_emission_wrapped_near() {
+        let context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_wrapped_near(accounts(2), wrapped_near_metadata());
+        contract.release_emission();
+    }
+
+    #[test]
+    #[should_panic(expected = "The operation Transfer is paused")]
+    fn test_near_withdraw_paused() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_wrapped_near(accounts(2), wrapped_near_metadata());
+        testing_env!(context.attached_deposit(10u128.pow(24)).build());
+        contract.near_deposit();
+        testing_env!(context.storage_usage(env::storage_usage()).attached_deposit(1).build());
+        contract.pause(Some(vec![PausableOperation::Transfer]));
+        contract.near_withdraw(1.into());
+    }
+
+    fn authorization_keypair(seed: u8) -> ed25519_dalek::Keypair {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        ed25519_dalek::Keypair { secret, public }
+    }
+
+    fn sign_authorization(
+        keypair: &ed25519_dalek::Keypair,
+        authorization: &TransferAuthorization,
+    ) -> Base64VecU8 {
+        use ed25519_dalek::Signer;
+        let message = (accounts(0), authorization).try_to_vec().unwrap();
+        keypair.sign(&message).to_bytes().to_vec().into()
+    }
+
+    /// Registers `accounts(1)` and the authorization key of `accounts(2)`, the owner.
+    fn setup_authorization(keypair: &ed25519_dalek::Keypair) -> (VMContextBuilder, Contract) {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.storage_deposit(None, None);
+        let public_key = [vec![0], keypair.public.to_bytes().to_vec()].concat();
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(EXTRA_STORAGE_DEPOSIT)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.set_authorization_key(public_key.try_into().unwrap());
+        // the relayer pays for gas
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(0)
+            .predecessor_account_id(accounts(3))
+            .block_timestamp(1_000)
+            .build());
+        (context, contract)
+    }
+
+    fn sample_authorization() -> TransferAuthorization {
+        TransferAuthorization {
+            from: accounts(2),
+            to: accounts(1),
+            amount: (TOTAL_SUPPLY / 4).into(),
+            nonce: U64(0),
+            deadline: U64(2_000),
+            memo: Some("gasless".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_transfer_with_authorization() {
+        let keypair = authorization_keypair(1);
+        let (_, mut contract) = setup_authorization(&keypair);
+        let authorization = sample_authorization();
+        let signature = sign_authorization(&keypair, &authorization);
+        contract.ft_transfer_with_authorization(authorization, signature);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY / 4);
+        assert_eq!(contract.ft_authorization_nonce(accounts(2)).0, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid nonce")]
+    fn test_transfer_with_authorization_replayed() {
+        let keypair = authorization_keypair(1);
+        let (_, mut contract) = setup_authorization(&keypair);
+        let authorization = sample_authorization();
+        let signature = sign_authorization(&keypair, &authorization);
+        contract.ft_transfer_with_authorization(authorization.clone(), signature.clone());
+        contract.ft_transfer_with_authorization(authorization, signature);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid signature")]
+    fn test_transfer_with_authorization_invalid_signature() {
+        let (_, mut contract) = setup_authorization(&authorization_keypair(1));
+        let authorization = sample_authorization();
+        let signature = sign_authorization(&authorization_keypair(2), &authorization);
+        contract.ft_transfer_with_authorization(authorization, signature);
+    }
+
+    #[test]
+    #[should_panic(expected = "The authorization has expired")]
+    fn test_transfer_with_authorization_expired() {
+        let keypair = authorization_keypair(1);
+        let (mut context, mut contract) = setup_authorization(&keypair);
+        let authorization = sample_authorization();
+        let signature = sign_authorization(&keypair, &authorization);
+        testing_env!(context.block_timestamp(2_001).build());
+        contract.ft_transfer_with_authorization(authorization, signature);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid signature")]
+    fn test_transfer_with_authorization_changed_memo() {
+        let keypair = authorization_keypair(1);
+        let (_, mut contract) = setup_authorization(&keypair);
+        let mut authorization = sample_authorization();
+        let signature = sign_authorization(&keypair, &authorization);
+        authorization.memo = Some("changed".to_string());
+        contract.ft_transfer_with_authorization(authorization, signature);
+    }
+
+    #[test]
+    fn test_remove_authorization_key() {
+        let (mut context, mut contract) = setup_authorization(&authorization_keypair(1));
+        testing_env!(context.predecessor_account_id(accounts(2)).attached_deposit(1).build());
+        contract.remove_authorization_key();
+        assert!(contract.get_authorization_key(accounts(2)).is_none());
+        let released = Balance::from(context.context.storage_usage - env::storage_usage())
+            * env::storage_byte_cost();
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        assert_eq!(receipts[0].receiver_id, accounts(2));
+        assert_eq!(
+            receipts[0].actions,
+            vec![near_sdk::mock::VmAction::Transfer { deposit: released + 1 }]
+        );
+    }
+
+    fn airdrop_leaf(account_id: AccountId, amount: Balance) -> Vec<u8> {
+        env::sha256(&(account_id, amount).try_to_vec().unwrap())
+    }
+
+    fn airdrop_parent(a: &[u8], b: &[u8]) -> Vec<u8> {
+        if a <= b {
+            env::sha256(&[a, b].concat())
+        } else {
+            env::sha256(&[b, a].concat())
+        }
+    }
+
+    /// Creates a campaign for `accounts(1)`, `accounts(3)` and `accounts(4)` that expires at
+    /// 1_000 and returns the proof of `accounts(1)`, which is registered.
+    fn setup_airdrop() -> (VMContextBuilder, Contract, Vec<Base64VecU8>) {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.storage_deposit(None, None);
+
+        let leaves = [
+            airdrop_leaf(accounts(1), 100),
+            airdrop_leaf(accounts(3), 200),
+            airdrop_leaf(accounts(4), 300),
+        ];
+        let root = airdrop_parent(&airdrop_parent(&leaves[0], &leaves[1]), &leaves[2]);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.create_airdrop(root.into(), U128(600), U64(1_000));
+        testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(1)).build());
+        let proof = vec![leaves[1].clone().into(), leaves[2].clone().into()];
+        (context, contract, proof)
+    }
+
+    #[test]
+    #[should_panic(expected = "The airdrop is already claimed")]
+    fn test_airdrop_claim() {
+        let (_, mut contract, proof) = setup_airdrop();
+        contract.claim(0, U128(100), proof.clone());
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 100);
+        assert!(contract.is_airdrop_claimed(0, accounts(1)));
+        assert_eq!(contract.get_airdrop(0).unwrap().claimed_amount.0, 100);
+        contract.claim(0, U128(100), proof);
+    }
+
+    #[test]
+    #[should_panic(expected = "The operation Transfer is paused")]
+    fn test_airdrop_claim_paused() {
+        let (mut context, mut contract, proof) = setup_airdrop();
+        testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(2)).build());
+        contract.pause(Some(vec![PausableOperation::Transfer]));
+        testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(1)).build());
+        contract.claim(0, U128(100), proof);
+    }
+
+    #[test]
+    fn test_airdrop_claim_two_leaves() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        for account_id in [accounts(1), accounts(3)] {
+            testing_env!(context
+                .storage_usage(env::storage_usage())
+                .attached_deposit(contract.storage_balance_bounds().min.into())
+                .predecessor_account_id(account_id)
+                .build());
+            contract.storage_deposit(None, None);
+        }
+        let leaves = [airdrop_leaf(accounts(1), 100), airdrop_leaf(accounts(3), 200)];
+        let root = airdrop_parent(&leaves[0], &leaves[1]);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.create_airdrop(root.into(), U128(300), U64(1_000));
+
+        testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(1)).build());
+        contract.claim(0, U128(100), vec![leaves[1].clone().into()]);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 100);
+        assert_eq!(contract.ft_balance_of(accounts(0)).0, 200);
+
+        let repeat = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            contract.claim(0, U128(100), vec![leaves[1].clone().into()])
+        }));
+        let message = repeat.unwrap_err().downcast::<String>().unwrap();
+        assert!(message.contains("The airdrop is already claimed"));
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 100);
+        assert_eq!(contract.ft_balance_of(accounts(0)).0, 200);
+        assert_eq!(contract.get_airdrop(0).unwrap().claimed_amount.0, 100);
+
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.claim(0, U128(200), vec![leaves[0].clone().into()]);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 200);
+        assert_eq!(contract.ft_balance_of(accounts(0)).0, 0);
+        assert_eq!(contract.get_airdrop(0).unwrap().claimed_amount.0, 300);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid merkle proof")]
+    fn test_airdrop_claim_invalid_proof() {
+        let (_, mut contract, proof) = setup_airdrop();
+        contract.claim(0, U128(200), proof);
+    }
+
+    #[test]
+    fn test_airdrop_sweep() {
+        let (mut context, mut contract, proof) = setup_airdrop();
+        contract.claim(0, U128(100), proof);
+        testing_env!(context
+            .block_timestamp(1_000)
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        assert_eq!(contract.sweep_airdrop(0).0, 500);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY - 100);
+        assert_eq!(contract.ft_balance_of(accounts(0)).0, 0);
+    }
+
+    #[test]
+    fn test_batch_transfer() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(contract.storage_balance_bounds().min.into())
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.storage_deposit(None, None);
+
+        // accounts(3) is registered from the attached deposit
+        let storage_deposit: Balance = contract.storage_balance_bounds().min.into();
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(storage_deposit + 1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.ft_batch_transfer(
+            vec![(accounts(1), U128(100)), (accounts(3), U128(200))],
+            Some("payroll".to_string()),
+            Some(true),
+        );
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 100);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 200);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY - 300);
+        let logs = get_logs();
+        assert_eq!(logs.len(), 1);
+        assert!(logs[0].starts_with("EVENT_JSON:{\"standard\":\"nep141\""));
+        assert_eq!(logs[0].matches("\"memo\":\"payroll\"").count(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not registered")]
+    fn test_batch_transfer_unregistered_receiver() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_batch_transfer(vec![(accounts(1), U128(100))], None, None);
+    }
+
+    #[test]
+    fn test_migrate_from_v1() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut token = FungibleToken::new(b"a".to_vec());
+        token.internal_register_account(&accounts(0));
+        token.internal_register_account(&accounts(1));
+        token.internal_deposit(&accounts(1), TOTAL_SUPPLY);
+        env::state_write(&ContractV1 {
+            token,
+            metadata: LazyOption::new(b"m".to_vec(), Some(&contract_metadata())),
+        });
+
+        let mut contract = Contract::migrate();
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY);
+        assert_eq!(contract.ft_metadata().symbol, "RLTS");
+        assert_eq!(contract.owner_id, accounts(0));
+
+        // the migrated state is read back in the current layout
+        testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(1)).build());
+        contract.ft_transfer(accounts(0), U128(100), None);
+        env::state_write(&contract);
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let contract = Contract::migrate();
+        assert_eq!(contract.ft_balance_of(accounts(0)).0, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_migrate_not_owner() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        env::state_write(&contract);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        Contract::migrate();
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough gas attached to migrate the state")]
+    fn test_upgrade_not_enough_gas() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        let mut vm_context =
+            context.attached_deposit(1).prepaid_gas(Gas(5_000_000_000_000)).build();
+        vm_context.input = vec![0; 8];
+        testing_env!(vm_context);
+        contract.upgrade();
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough gas attached to migrate the state")]
+    fn test_upgrade_too_little_gas_for_migrate() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        // enough for `upgrade` itself, but it would leave less than `MIN_GAS_FOR_MIGRATE`
+        let mut vm_context =
+            context.attached_deposit(1).prepaid_gas(Gas(25_000_000_000_000)).build();
+        vm_context.input = vec![0; 8];
+        testing_env!(vm_context);
+        contract.upgrade();
+    }
 }
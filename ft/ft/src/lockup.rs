@@ -0,0 +1,106 @@
+//! Locked balances for team and advisor allocations.
+//!
+//! The owner sends tokens with `ft_transfer_locked`. Nothing unlocks before the cliff, after that
+//! the amount unlocks linearly from `start` until `start + duration`. `ft_balance_of` still
+//! reports the full balance, but only the unlocked part can leave the account.
+use crate::math::mul_div;
+use crate::*;
+use near_sdk::json_types::U64;
+use near_sdk::serde::{Deserialize, Serialize};
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Lockup {
+    pub amount: U128,
+    /// Timestamp the linear release starts from, in nanoseconds.
+    pub start: U64,
+    /// Time after `start` before anything unlocks, in nanoseconds.
+    pub cliff_duration: U64,
+    /// Time after `start` when everything is unlocked, in nanoseconds.
+    pub duration: U64,
+}
+
+impl Lockup {
+    /// Returns the part of the lockup that is still locked at `timestamp`.
+    fn locked_amount(&self, timestamp: u64) -> Balance {
+        let elapsed = timestamp.saturating_sub(self.start.0);
+        if elapsed < self.cliff_duration.0 {
+            self.amount.0
+        } else if elapsed >= self.duration.0 {
+            0
+        } else {
+            let unlocked =
+                mul_div(self.amount.0, Balance::from(elapsed), Balance::from(self.duration.0));
+            self.amount.0 - unlocked
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Transfers `lockup.amount` tokens from the owner to `receiver_id` and locks them in the
+    /// receiver's account. Only the owner can call this.
+    #[payable]
+    pub fn ft_transfer_locked(
+        &mut self,
+        receiver_id: AccountId,
+        lockup: Lockup,
+        memo: Option<String>,
+    ) {
+        assert_one_yocto();
+        self.assert_not_paused(PausableOperation::Transfer);
+        self.assert_owner();
+        require!(lockup.duration.0 > 0, "The duration should be positive");
+        require!(
+            lockup.cliff_duration.0 <= lockup.duration.0,
+            "The cliff can't be longer than the duration"
+        );
+        let owner_id = self.owner_id.clone();
+        self.internal_transfer(&owner_id, &receiver_id, lockup.amount.0, memo);
+        let mut lockups = self.lockups.get(&receiver_id).unwrap_or_default();
+        lockups.push(lockup);
+        self.lockups.insert(&receiver_id, &lockups);
+    }
+
+    /// Returns the part of the balance of `account_id` that can be transferred.
+    pub fn ft_unlocked_balance_of(&self, account_id: AccountId) -> U128 {
+        let balance = self.token.accounts.get(&account_id).unwrap_or(0);
+        balance.saturating_sub(self.internal_locked_balance_of(&account_id)).into()
+    }
+
+    pub fn ft_lockups_of(&self, account_id: AccountId) -> Vec<Lockup> {
+        self.lockups.get(&account_id).unwrap_or_default()
+    }
+}
+
+impl Contract {
+    fn internal_locked_balance_of(&self, account_id: &AccountId) -> Balance {
+        let timestamp = env::block_timestamp();
+        self.lockups
+            .get(account_id)
+            .unwrap_or_default()
+            .iter()
+            .map(|lockup| lockup.locked_amount(timestamp))
+            .sum()
+    }
+
+    /// Checks that `account_id` can send `amount` tokens, dropping its lockups once they are
+    /// fully released.
+    pub(crate) fn internal_assert_unlocked(&mut self, account_id: &AccountId, amount: Balance) {
+        let lockups = match self.lockups.get(account_id) {
+            Some(lockups) => lockups,
+            None => return,
+        };
+        let timestamp = env::block_timestamp();
+        let locked: Balance = lockups.iter().map(|lockup| lockup.locked_amount(timestamp)).sum();
+        if locked == 0 {
+            self.lockups.remove(account_id);
+            return;
+        }
+        let unlocked = self.token.accounts.get(account_id).unwrap_or(0).saturating_sub(locked);
+        require!(
+            amount <= unlocked,
+            format!("The account {} only has {} unlocked tokens", account_id, unlocked)
+        );
+    }
+}
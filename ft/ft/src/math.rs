@@ -0,0 +1,13 @@
+#![allow(clippy::all)]
+//! 256 bits integers for products of balances, which don't fit in a `u128` with 24 decimals.
+use near_sdk::Balance;
+
+uint::construct_uint! {
+    pub struct U256(4);
+}
+
+/// Returns `a * b / c`, rounded down. The result must fit in a `u128`, which it does when
+/// `b <= c`.
+pub(crate) fn mul_div(a: Balance, b: Balance, c: Balance) -> Balance {
+    (U256::from(a) * U256::from(b) / U256::from(c)).as_u128()
+}
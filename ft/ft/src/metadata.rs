@@ -0,0 +1,58 @@
+//! Owner-managed token metadata.
+//!
+//! Every update is validated with `assert_valid` and logged as an `ft_metadata_update` event, so
+//! wallets and indexers know to fetch `ft_metadata` again.
+use crate::events::FtMetadataUpdate;
+use crate::*;
+use near_sdk::json_types::Base64VecU8;
+
+#[near_bindgen]
+impl Contract {
+    /// Only the owner can call this.
+    #[payable]
+    pub fn set_name(&mut self, name: String) {
+        self.internal_update_metadata(&["name"], |metadata| metadata.name = name);
+    }
+
+    /// Only the owner can call this.
+    #[payable]
+    pub fn set_symbol(&mut self, symbol: String) {
+        self.internal_update_metadata(&["symbol"], |metadata| metadata.symbol = symbol);
+    }
+
+    /// Sets the icon as a data URL, or removes it with `None`. Only the owner can call this.
+    #[payable]
+    pub fn set_icon(&mut self, icon: Option<String>) {
+        self.internal_update_metadata(&["icon"], |metadata| metadata.icon = icon);
+    }
+
+    /// Sets the off-chain reference together with its sha256 hash, or removes both with `None`.
+    /// Only the owner can call this.
+    #[payable]
+    pub fn set_reference(
+        &mut self,
+        reference: Option<String>,
+        reference_hash: Option<Base64VecU8>,
+    ) {
+        self.internal_update_metadata(&["reference", "reference_hash"], |metadata| {
+            metadata.reference = reference;
+            metadata.reference_hash = reference_hash;
+        });
+    }
+}
+
+impl Contract {
+    fn internal_update_metadata(
+        &mut self,
+        fields: &[&str],
+        update: impl FnOnce(&mut FungibleTokenMetadata),
+    ) {
+        assert_one_yocto();
+        self.assert_owner();
+        let mut metadata = self.metadata.get().unwrap();
+        update(&mut metadata);
+        metadata.assert_valid();
+        self.metadata.set(&metadata);
+        FtMetadataUpdate { fields }.emit();
+    }
+}
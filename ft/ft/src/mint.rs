@@ -0,0 +1,104 @@
+use crate::*;
+use near_contract_standards::fungible_token::events::{FtBurn, FtMint};
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the account that manages the minter registry.
+    pub fn get_owner(&self) -> AccountId {
+        self.owner_id.clone()
+    }
+
+    /// Hands the contract ownership over to `owner_id`. Only the current owner can call this.
+    #[payable]
+    pub fn set_owner(&mut self, owner_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.owner_id = owner_id;
+    }
+
+    /// Grants the minter role to `account_id`. Only the owner can call this.
+    #[payable]
+    pub fn add_minter(&mut self, account_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(self.minters.insert(&account_id), "The account is already a minter");
+    }
+
+    /// Revokes the minter role from `account_id`. Only the owner can call this.
+    #[payable]
+    pub fn remove_minter(&mut self, account_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(self.minters.remove(&account_id), "The account is not a minter");
+    }
+
+    pub fn is_minter(&self, account_id: AccountId) -> bool {
+        self.minters.contains(&account_id)
+    }
+
+    pub fn get_minters(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<AccountId> {
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+        self.minters
+            .iter()
+            .skip(start as usize)
+            .take(limit.unwrap_or(self.minters.len()) as usize)
+            .collect()
+    }
+
+    /// Mints `amount` new tokens to the registered `account_id`. Only minters can call this.
+    #[payable]
+    pub fn ft_mint(&mut self, account_id: AccountId, amount: U128, memo: Option<String>) {
+        assert_one_yocto();
+        self.assert_minter();
+        self.assert_not_wrapped_near();
+        self.internal_mint(&account_id, amount.into(), memo.as_deref());
+    }
+
+    /// Burns `amount` tokens from the caller's balance. Only minters can call this.
+    #[payable]
+    pub fn ft_burn(&mut self, amount: U128, memo: Option<String>) {
+        assert_one_yocto();
+        self.assert_minter();
+        let account_id = env::predecessor_account_id();
+        self.internal_burn(&account_id, amount.into(), memo.as_deref());
+    }
+}
+
+impl Contract {
+    pub(crate) fn assert_minter(&self) {
+        require!(
+            self.minters.contains(&env::predecessor_account_id()),
+            "Only minters can call this method"
+        );
+    }
+
+    pub(crate) fn internal_mint(
+        &mut self,
+        account_id: &AccountId,
+        amount: Balance,
+        memo: Option<&str>,
+    ) {
+        require!(amount > 0, "The amount should be a positive number");
+        if let Some(max_supply) = self.max_supply {
+            require!(
+                self.token.total_supply.saturating_add(amount) <= max_supply,
+                "The amount exceeds the maximum supply"
+            );
+        }
+        self.internal_before_transfer(None, Some(account_id), amount);
+        self.token.internal_deposit(account_id, amount);
+        FtMint { owner_id: account_id, amount: &U128(amount), memo }.emit();
+    }
+
+    pub(crate) fn internal_burn(
+        &mut self,
+        account_id: &AccountId,
+        amount: Balance,
+        memo: Option<&str>,
+    ) {
+        require!(amount > 0, "The amount should be a positive number");
+        self.internal_before_transfer(Some(account_id), None, amount);
+        self.token.internal_withdraw(account_id, amount);
+        FtBurn { owner_id: account_id, amount: &U128(amount), memo }.emit();
+    }
+}
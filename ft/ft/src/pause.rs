@@ -0,0 +1,92 @@
+use crate::events::PauseChanged;
+use crate::*;
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// Operations that the owner or the guardian can halt in an emergency.
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug,
+)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+pub enum PausableOperation {
+    Transfer,
+    TransferCall,
+    StorageUnregister,
+}
+
+const ALL_OPERATIONS: [PausableOperation; 3] = [
+    PausableOperation::Transfer,
+    PausableOperation::TransferCall,
+    PausableOperation::StorageUnregister,
+];
+
+#[near_bindgen]
+impl Contract {
+    pub fn get_guardian(&self) -> Option<AccountId> {
+        self.guardian_id.clone()
+    }
+
+    /// Sets or clears the guardian account. Only the owner can call this.
+    #[payable]
+    pub fn set_guardian(&mut self, guardian_id: Option<AccountId>) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.guardian_id = guardian_id;
+    }
+
+    /// Returns the operations that are currently paused.
+    pub fn get_paused_operations(&self) -> Vec<PausableOperation> {
+        self.paused_operations.clone()
+    }
+
+    /// Pauses the given `operations`, or every pausable operation if none are given.
+    /// Only the owner or the guardian can call this.
+    #[payable]
+    pub fn pause(&mut self, operations: Option<Vec<PausableOperation>>) {
+        assert_one_yocto();
+        self.assert_owner_or_guardian();
+        for operation in operations.unwrap_or_else(|| ALL_OPERATIONS.to_vec()) {
+            if !self.paused_operations.contains(&operation) {
+                self.paused_operations.push(operation);
+            }
+        }
+        self.emit_pause_changed();
+    }
+
+    /// Unpauses the given `operations`, or every paused operation if none are given.
+    /// Only the owner or the guardian can call this.
+    #[payable]
+    pub fn unpause(&mut self, operations: Option<Vec<PausableOperation>>) {
+        assert_one_yocto();
+        self.assert_owner_or_guardian();
+        match operations {
+            Some(operations) => self.paused_operations.retain(|op| !operations.contains(op)),
+            None => self.paused_operations.clear(),
+        }
+        self.emit_pause_changed();
+    }
+}
+
+impl Contract {
+    pub(crate) fn assert_not_paused(&self, operation: PausableOperation) {
+        require!(
+            !self.paused_operations.contains(&operation),
+            format!("The operation {:?} is paused", operation)
+        );
+    }
+
+    fn assert_owner_or_guardian(&self) {
+        let predecessor_id = env::predecessor_account_id();
+        require!(
+            predecessor_id == self.owner_id || Some(&predecessor_id) == self.guardian_id.as_ref(),
+            "Only the owner or the guardian can call this method"
+        );
+    }
+
+    fn emit_pause_changed(&self) {
+        PauseChanged {
+            account_id: &env::predecessor_account_id(),
+            paused_operations: &self.paused_operations,
+        }
+        .emit();
+    }
+}
@@ -0,0 +1,103 @@
+//! Balance snapshots for token-weighted votes.
+//!
+//! The owner takes a snapshot with `ft_snapshot`, which only bumps the current snapshot id.
+//! Balances are recorded lazily: the first time an account balance (or the total supply) changes
+//! after a snapshot, the value it had before the change is stored under the current id.
+//! Reading a snapshot returns the first value recorded at or after that id, or the live value if
+//! nothing changed since.
+use crate::events::SnapshotCreated;
+use crate::*;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Snapshot {
+    id: u64,
+    value: Balance,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Takes a new snapshot of all balances and returns its id. Only the owner can call this.
+    #[payable]
+    pub fn ft_snapshot(&mut self) -> u64 {
+        assert_one_yocto();
+        self.assert_owner();
+        self.current_snapshot_id += 1;
+        SnapshotCreated {
+            snapshot_id: self.current_snapshot_id,
+            block_height: env::block_height(),
+        }
+        .emit();
+        self.current_snapshot_id
+    }
+
+    pub fn ft_current_snapshot_id(&self) -> u64 {
+        self.current_snapshot_id
+    }
+
+    /// Returns the balance `account_id` had when snapshot `snapshot_id` was taken.
+    pub fn ft_balance_of_at(&self, account_id: AccountId, snapshot_id: u64) -> U128 {
+        self.assert_valid_snapshot_id(snapshot_id);
+        let count = self.account_snapshot_counts.get(&account_id).unwrap_or(0);
+        let snapshot =
+            |index: u64| self.account_snapshots.get(&(account_id.clone(), index as u32)).unwrap();
+        let index = partition_point(u64::from(count), |index| snapshot(index).id < snapshot_id);
+        let balance = if index < u64::from(count) {
+            snapshot(index).value
+        } else {
+            self.token.accounts.get(&account_id).unwrap_or(0)
+        };
+        balance.into()
+    }
+
+    /// Returns the total supply when snapshot `snapshot_id` was taken.
+    pub fn ft_total_supply_at(&self, snapshot_id: u64) -> U128 {
+        self.assert_valid_snapshot_id(snapshot_id);
+        let index = partition_point(self.total_supply_snapshots.len(), |index| {
+            self.total_supply_snapshots.get(index).unwrap().id < snapshot_id
+        });
+        self.total_supply_snapshots
+            .get(index)
+            .map(|snapshot| snapshot.value)
+            .unwrap_or(self.token.total_supply)
+            .into()
+    }
+}
+
+impl Contract {
+    fn assert_valid_snapshot_id(&self, snapshot_id: u64) {
+        require!(snapshot_id > 0, "The snapshot id should be a positive number");
+        require!(snapshot_id <= self.current_snapshot_id, "The snapshot doesn't exist yet");
+    }
+
+    pub(crate) fn internal_update_account_snapshot(&mut self, account_id: &AccountId) {
+        if self.current_snapshot_id == 0 {
+            return;
+        }
+        let count = self.account_snapshot_counts.get(account_id).unwrap_or(0);
+        let last = count
+            .checked_sub(1)
+            .and_then(|last| self.account_snapshots.get(&(account_id.clone(), last)));
+        if last.map(|last| last.id) != Some(self.current_snapshot_id) {
+            self.account_snapshots.insert(
+                &(account_id.clone(), count),
+                &Snapshot {
+                    id: self.current_snapshot_id,
+                    value: self.token.accounts.get(account_id).unwrap_or(0),
+                },
+            );
+            self.account_snapshot_counts.insert(account_id, &(count + 1));
+        }
+    }
+
+    pub(crate) fn internal_update_total_supply_snapshot(&mut self) {
+        if self.current_snapshot_id == 0 {
+            return;
+        }
+        let len = self.total_supply_snapshots.len();
+        let last = len.checked_sub(1).and_then(|last| self.total_supply_snapshots.get(last));
+        if last.map(|last| last.id) != Some(self.current_snapshot_id) {
+            self.total_supply_snapshots
+                .push(&Snapshot { id: self.current_snapshot_id, value: self.token.total_supply });
+        }
+    }
+}
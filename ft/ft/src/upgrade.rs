@@ -0,0 +1,102 @@
+//! Code upgrades and state migrations.
+//!
+//! The owner calls `upgrade` with the new wasm as the raw input of the call. The contract deploys
+//! it on its own account and calls `migrate`, which reads the state in whatever layout it was
+//! stored and rewrites it in the current one. `VersionedContract` lists every layout the contract
+//! was deployed with: when a field is added to `Contract`, add a variant with the previous layout
+//! and its conversion before deploying.
+use crate::*;
+use near_sdk::Gas;
+
+/// Gas kept by `upgrade` for itself, the rest of the prepaid gas goes to `migrate`.
+const GAS_FOR_UPGRADE: Gas = Gas(10_000_000_000_000);
+
+/// Least gas `upgrade` leaves to `migrate`, checked before the code is deployed.
+const MIN_GAS_FOR_MIGRATE: Gas = Gas(20_000_000_000_000);
+
+const STATE_KEY: &[u8] = b"STATE";
+
+/// Layout of the first deployments, before the owner and the extensions were added.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ContractV1 {
+    pub token: FungibleToken,
+    pub metadata: LazyOption<FungibleTokenMetadata>,
+}
+
+pub enum VersionedContract {
+    V1(ContractV1),
+    Current(Box<Contract>),
+}
+
+impl VersionedContract {
+    /// Reads the stored state, trying the layouts from the newest to the oldest. Borsh requires
+    /// the whole state to be consumed, so a layout with different fields doesn't match.
+    fn read() -> Self {
+        let state = env::storage_read(STATE_KEY)
+            .unwrap_or_else(|| env::panic_str("The contract is not initialized"));
+        if let Ok(contract) = Contract::try_from_slice(&state) {
+            return Self::Current(Box::new(contract));
+        }
+        if let Ok(contract) = ContractV1::try_from_slice(&state) {
+            return Self::V1(contract);
+        }
+        env::panic_str("The state has an unknown layout")
+    }
+
+    /// Account allowed to migrate the state. Layouts without an owner are migrated by the
+    /// contract account itself, which then becomes the owner.
+    fn owner_id(&self) -> AccountId {
+        match self {
+            Self::V1(_) => env::current_account_id(),
+            Self::Current(contract) => contract.owner_id.clone(),
+        }
+    }
+}
+
+impl From<VersionedContract> for Contract {
+    fn from(state: VersionedContract) -> Self {
+        match state {
+            VersionedContract::V1(ContractV1 { token, metadata }) => {
+                Contract::from_token(token, metadata, env::current_account_id())
+            }
+            VersionedContract::Current(contract) => *contract,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Rewrites the stored state in the current layout. Called by `upgrade` after deploying new
+    /// code, or by the owner directly.
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let state = VersionedContract::read();
+        let predecessor_id = env::predecessor_account_id();
+        require!(
+            predecessor_id == env::current_account_id() || predecessor_id == state.owner_id(),
+            "Only the owner can call this method"
+        );
+        state.into()
+    }
+
+    /// Deploys the wasm passed as the raw input of the call and migrates the state to it. Only
+    /// the owner can call this.
+    #[payable]
+    pub fn upgrade(&mut self) -> Promise {
+        assert_one_yocto();
+        self.assert_owner();
+        let code = env::input().unwrap_or_else(|| env::panic_str("The code is missing"));
+        let migrate_gas =
+            env::prepaid_gas().0.saturating_sub(env::used_gas().0 + GAS_FOR_UPGRADE.0);
+        require!(
+            migrate_gas >= MIN_GAS_FOR_MIGRATE.0,
+            "Not enough gas attached to migrate the state"
+        );
+        Promise::new(env::current_account_id()).deploy_contract(code).function_call(
+            "migrate".to_string(),
+            Vec::new(),
+            0,
+            Gas(migrate_gas),
+        )
+    }
+}
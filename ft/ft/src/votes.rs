@@ -0,0 +1,124 @@
+//! ERC20Votes-like vote delegation.
+//!
+//! Holders delegate their voting power to an account (possibly themselves) without moving tokens.
+//! Only delegated balances count as votes. Every delegate keeps a list of checkpoints, one per block
+//! height at which its voting power changed, so past votes can be read at any block height.
+use crate::events::DelegateChanged;
+use crate::*;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Checkpoint {
+    block_height: u64,
+    votes: Balance,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Delegates the caller's voting power to `delegatee`. Delegating to yourself is needed to
+    /// vote with your own balance. The attached deposit has to cover any new storage.
+    #[payable]
+    pub fn delegate(&mut self, delegatee: AccountId) {
+        let initial_storage_usage = env::storage_usage();
+        let delegator = env::predecessor_account_id();
+        let balance = self.token.internal_unwrap_balance_of(&delegator);
+        let previous_delegatee = self.delegations.insert(&delegator, &delegatee);
+        DelegateChanged {
+            delegator: &delegator,
+            from_delegate: previous_delegatee.as_ref(),
+            to_delegate: &delegatee,
+        }
+        .emit();
+        self.internal_move_votes(previous_delegatee.as_ref(), Some(&delegatee), balance);
+        refund_deposit(initial_storage_usage);
+    }
+
+    /// Returns the account `account_id` delegates its voting power to.
+    pub fn delegates(&self, account_id: AccountId) -> Option<AccountId> {
+        self.delegations.get(&account_id)
+    }
+
+    /// Returns the current voting power of `account_id`.
+    pub fn get_votes(&self, account_id: AccountId) -> U128 {
+        self.internal_current_votes(&account_id).into()
+    }
+
+    /// Returns the voting power `account_id` had at the end of block `block_height`, which must
+    /// be in the past.
+    pub fn get_past_votes(&self, account_id: AccountId, block_height: u64) -> U128 {
+        require!(block_height < env::block_height(), "The block is not yet finalized");
+        let count = self.vote_checkpoint_counts.get(&account_id).unwrap_or(0);
+        let checkpoint =
+            |index: u64| self.vote_checkpoints.get(&(account_id.clone(), index as u32)).unwrap();
+        let index = partition_point(u64::from(count), |index| {
+            checkpoint(index).block_height <= block_height
+        });
+        index.checked_sub(1).map(|index| checkpoint(index).votes).unwrap_or(0).into()
+    }
+}
+
+impl Contract {
+    /// Moves the voting power of a balance change over to the new holder's delegate.
+    pub(crate) fn internal_move_delegate_votes(
+        &mut self,
+        sender_id: Option<&AccountId>,
+        receiver_id: Option<&AccountId>,
+        amount: Balance,
+    ) {
+        let from = sender_id.and_then(|account_id| self.delegations.get(account_id));
+        let to = receiver_id.and_then(|account_id| self.delegations.get(account_id));
+        self.internal_move_votes(from.as_ref(), to.as_ref(), amount);
+    }
+
+    fn internal_move_votes(
+        &mut self,
+        from: Option<&AccountId>,
+        to: Option<&AccountId>,
+        amount: Balance,
+    ) {
+        if from == to || amount == 0 {
+            return;
+        }
+        if let Some(from) = from {
+            self.internal_write_checkpoint(from, |votes| {
+                votes.checked_sub(amount).unwrap_or_else(|| env::panic_str("Votes underflow"))
+            });
+        }
+        if let Some(to) = to {
+            self.internal_write_checkpoint(to, |votes| {
+                votes.checked_add(amount).unwrap_or_else(|| env::panic_str("Votes overflow"))
+            });
+        }
+    }
+
+    fn internal_last_checkpoint(&self, delegatee: &AccountId) -> Option<(u32, Checkpoint)> {
+        let count = self.vote_checkpoint_counts.get(delegatee).unwrap_or(0);
+        let index = count.checked_sub(1)?;
+        self.vote_checkpoints.get(&(delegatee.clone(), index)).map(|checkpoint| (index, checkpoint))
+    }
+
+    fn internal_current_votes(&self, delegatee: &AccountId) -> Balance {
+        self.internal_last_checkpoint(delegatee)
+            .map(|(_, checkpoint)| checkpoint.votes)
+            .unwrap_or(0)
+    }
+
+    /// Records the updated voting power of `delegatee`, overwriting the last checkpoint if it's
+    /// from the current block.
+    fn internal_write_checkpoint(
+        &mut self,
+        delegatee: &AccountId,
+        update: impl FnOnce(Balance) -> Balance,
+    ) {
+        let block_height = env::block_height();
+        let last = self.internal_last_checkpoint(delegatee);
+        let votes = update(last.as_ref().map(|(_, checkpoint)| checkpoint.votes).unwrap_or(0));
+        let index = match last {
+            Some((index, last)) if last.block_height == block_height => index,
+            Some((index, _)) => index + 1,
+            None => 0,
+        };
+        self.vote_checkpoints
+            .insert(&(delegatee.clone(), index), &Checkpoint { block_height, votes });
+        self.vote_checkpoint_counts.insert(delegatee, &(index + 1));
+    }
+}
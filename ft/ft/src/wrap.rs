@@ -0,0 +1,67 @@
+//! Wrapped NEAR mode.
+//!
+//! A contract initialized with `new_wrapped_near` starts with no supply and is fully backed by the
+//! NEAR it holds: `near_deposit` mints one token per attached yocto and `near_withdraw` burns
+//! tokens and sends the same amount of yocto back. The other initializers leave this mode off.
+//! Minting and emission are disabled in this mode, so every token stays backed by NEAR.
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Initializes a wrapped NEAR token owned by `owner_id`. The metadata must use 24 decimals,
+    /// like NEAR itself.
+    #[init]
+    pub fn new_wrapped_near(owner_id: AccountId, metadata: FungibleTokenMetadata) -> Self {
+        require!(metadata.decimals == 24, "Wrapped NEAR must have 24 decimals");
+        let mut this = Self::new(owner_id, U128(0), metadata, None);
+        this.wrapped_near = true;
+        this
+    }
+
+    /// Returns whether the contract wraps NEAR.
+    pub fn is_wrapped_near(&self) -> bool {
+        self.wrapped_near
+    }
+
+    /// Mints one token per attached yocto to the caller. An unregistered caller is registered
+    /// first, and the storage deposit is taken out of the attached deposit like
+    /// `storage_deposit` would.
+    #[payable]
+    pub fn near_deposit(&mut self) {
+        self.assert_wrapped_near();
+        self.assert_not_paused(PausableOperation::Transfer);
+        let account_id = env::predecessor_account_id();
+        let mut amount = env::attached_deposit();
+        if !self.token.accounts.contains_key(&account_id) {
+            let min_balance = self.token.storage_balance_bounds().min.0;
+            require!(
+                amount > min_balance,
+                "The attached deposit should cover the storage deposit and a positive amount"
+            );
+            self.token.internal_register_account(&account_id);
+            amount -= min_balance;
+        }
+        self.internal_mint(&account_id, amount, Some("near_deposit"));
+    }
+
+    /// Burns `amount` tokens of the caller and sends the same amount of yocto back to it.
+    #[payable]
+    pub fn near_withdraw(&mut self, amount: U128) -> Promise {
+        assert_one_yocto();
+        self.assert_wrapped_near();
+        self.assert_not_paused(PausableOperation::Transfer);
+        let account_id = env::predecessor_account_id();
+        self.internal_burn(&account_id, amount.0, Some("near_withdraw"));
+        Promise::new(account_id).transfer(amount.0)
+    }
+}
+
+impl Contract {
+    fn assert_wrapped_near(&self) {
+        require!(self.wrapped_near, "The contract doesn't wrap NEAR");
+    }
+
+    pub(crate) fn assert_not_wrapped_near(&self) {
+        require!(!self.wrapped_near, "Wrapped NEAR can only be minted by depositing NEAR");
+    }
+}
@@ -18,6 +18,7 @@ async fn main() -> anyhow::Result<()> {
     let defi_contract = worker.dev_deploy(&defi_wasm).await?;
     let ft_wasm = std::fs::read(FT_WASM_FILEPATH)?;
     let ft_contract = worker.dev_deploy(&ft_wasm).await?;
+    let wnear_contract = worker.dev_deploy(&ft_wasm).await?;
 
     // create accounts
     let owner = worker.root_account();
@@ -55,6 +56,19 @@ async fn main() -> anyhow::Result<()> {
         }))?
         .transact()
         .await?;
+    wnear_contract
+        .call(&worker, "new_wrapped_near")
+        .args_json(serde_json::json!({
+            "owner_id": owner.id(),
+            "metadata": {
+                "spec": "ft-1.0.0",
+                "name": "Wrapped NEAR",
+                "symbol": "wNEAR",
+                "decimals": 24,
+            },
+        }))?
+        .transact()
+        .await?;
     defi_contract
         .call(&worker, "new")
         .args_json(serde_json::json!({
@@ -96,6 +110,8 @@ async fn main() -> anyhow::Result<()> {
     .await?;
     test_transfer_call_promise_panics_for_a_full_refund(&owner, &alice, &ft_contract, &worker)
         .await?;
+    test_near_deposit_and_withdraw_round_trip(&bob, &wnear_contract, &worker).await?;
+    test_near_deposit_not_wrapped_near(&bob, &ft_contract, &worker).await?;
     Ok(())
 }
 
@@ -505,3 +521,85 @@ async fn test_transfer_call_promise_panics_for_a_full_refund(
     }
     Ok(())
 }
+
+async fn test_near_deposit_and_withdraw_round_trip(
+    user: &Account,
+    wnear_contract: &Contract,
+    worker: &Worker<Sandbox>,
+) -> anyhow::Result<()> {
+    let deposit = parse_near!("5 N");
+    let storage_bounds: serde_json::Value = wnear_contract
+        .call(&worker, "storage_balance_bounds")
+        .args_json(json!({}))?
+        .transact()
+        .await?
+        .json()?;
+    let storage_deposit: u128 = storage_bounds["min"].as_str().unwrap().parse()?;
+
+    // an unregistered account pays for its storage out of the deposit
+    user.call(&worker, wnear_contract.id(), "near_deposit")
+        .args_json(json!({}))?
+        .deposit(deposit)
+        .transact()
+        .await?;
+    let wrapped_balance: U128 = wnear_contract
+        .call(&worker, "ft_balance_of")
+        .args_json(json!({"account_id": user.id()}))?
+        .transact()
+        .await?
+        .json()?;
+    assert_eq!(wrapped_balance.0, deposit - storage_deposit);
+
+    let near_before_withdraw = user.view_account(&worker).await?.balance;
+    user.call(&worker, wnear_contract.id(), "near_withdraw")
+        .args_json(json!({"amount": wrapped_balance}))?
+        .deposit(1)
+        .transact()
+        .await?;
+    let near_after_withdraw = user.view_account(&worker).await?.balance;
+
+    let wrapped_balance: U128 = wnear_contract
+        .call(&worker, "ft_balance_of")
+        .args_json(json!({"account_id": user.id()}))?
+        .transact()
+        .await?
+        .json()?;
+    let total_supply: U128 = wnear_contract
+        .call(&worker, "ft_total_supply")
+        .args_json(json!({}))?
+        .transact()
+        .await?
+        .json()?;
+    assert_eq!(wrapped_balance.0, 0);
+    assert_eq!(total_supply.0, 0);
+    // the user gets the NEAR back, minus the gas of the withdraw call
+    assert!(
+        near_after_withdraw
+            > near_before_withdraw + deposit - storage_deposit - parse_near!("0.01 N")
+    );
+    println!("      Passed ✅ test_near_deposit_and_withdraw_round_trip");
+    Ok(())
+}
+
+async fn test_near_deposit_not_wrapped_near(
+    user: &Account,
+    ft_contract: &Contract,
+    worker: &Worker<Sandbox>,
+) -> anyhow::Result<()> {
+    match user
+        .call(&worker, ft_contract.id(), "near_deposit")
+        .args_json(json!({}))?
+        .deposit(parse_near!("1 N"))
+        .transact()
+        .await
+    {
+        Ok(_result) => {
+            panic!("near_deposit worked on a token that doesn't wrap NEAR")
+        }
+        Err(e) => {
+            assert!(e.to_string().contains("The contract doesn't wrap NEAR"));
+            println!("      Passed ✅ test_near_deposit_not_wrapped_near");
+        }
+    }
+    Ok(())
+}
@@ -18,6 +18,7 @@ mod internal;
 mod nft_callbacks;
 mod sale;
 mod sale_views;
+mod upgrade;
 
 //GAS constants to attach to calls
 const GAS_FOR_ROYALTIES: Gas = Gas(115_000_000_000_000);
@@ -183,3 +184,63 @@ impl Contract {
         U128(self.storage_deposits.get(&account_id).unwrap_or(0))
     }
 }
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    fn get_context(predecessor_account_id: AccountId) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .signer_account_id(predecessor_account_id.clone())
+            .predecessor_account_id(predecessor_account_id);
+        builder
+    }
+
+    //layout of the deployed marketplace, frozen so that changing `Contract` without a migration
+    //fails `test_migrate_from_v0`
+    #[derive(BorshSerialize)]
+    struct ContractV0 {
+        owner_id: AccountId,
+        sales: UnorderedMap<ContractAndTokenId, Sale>,
+        by_owner_id: LookupMap<AccountId, UnorderedSet<ContractAndTokenId>>,
+        by_nft_contract_id: LookupMap<AccountId, UnorderedSet<TokenId>>,
+        storage_deposits: LookupMap<AccountId, Balance>,
+    }
+
+    #[test]
+    fn test_migrate_from_v0() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut storage_deposits = LookupMap::new(StorageKey::StorageDeposits);
+        storage_deposits.insert(&accounts(2), &STORAGE_PER_SALE);
+        env::state_write(&ContractV0 {
+            owner_id: accounts(1),
+            sales: UnorderedMap::new(StorageKey::Sales),
+            by_owner_id: LookupMap::new(StorageKey::ByOwnerId),
+            by_nft_contract_id: LookupMap::new(StorageKey::ByNFTContractId),
+            storage_deposits,
+        });
+
+        let contract = Contract::migrate();
+        assert_eq!(contract.owner_id, accounts(1));
+        assert_eq!(contract.storage_balance_of(accounts(2)), U128(STORAGE_PER_SALE));
+        assert_eq!(contract.get_supply_sales(), U64(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_migrate_not_owner() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let contract = Contract::new(accounts(1));
+        env::state_write(&contract);
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        Contract::migrate();
+    }
+}
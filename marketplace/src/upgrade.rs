@@ -0,0 +1,89 @@
+//the owner upgrades the marketplace by passing the new wasm to `upgrade`. Sales and storage
+//deposits live in their own collections, so `migrate` only has to rewrite the `Contract` root.
+use crate::*;
+use near_sdk::require;
+
+//gas kept by `upgrade` for itself. The rest of the prepaid gas is attached to `migrate`
+const GAS_FOR_UPGRADE: Gas = Gas(10_000_000_000_000);
+
+//the least gas `migrate` gets. `upgrade` fails before deploying anything below that
+const MIN_GAS_FOR_MIGRATE: Gas = Gas(20_000_000_000_000);
+
+const STATE_KEY: &[u8] = b"STATE";
+
+pub enum VersionedContract {
+    Current(Contract),
+}
+
+impl VersionedContract {
+    //read the stored state in whichever layout it was written
+    fn read() -> Self {
+        let state = env::storage_read(STATE_KEY)
+            .unwrap_or_else(|| env::panic_str("The contract is not initialized"));
+        if let Ok(contract) = Contract::try_from_slice(&state) {
+            return Self::Current(contract);
+        }
+        env::panic_str("The state has an unknown layout")
+    }
+
+    //the account allowed to migrate the state next to the contract account itself
+    fn owner_id(&self) -> AccountId {
+        match self {
+            Self::Current(contract) => contract.owner_id.clone(),
+        }
+    }
+}
+
+impl From<VersionedContract> for Contract {
+    fn from(state: VersionedContract) -> Self {
+        match state {
+            VersionedContract::Current(contract) => contract,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    //rewrite the stored state in the current layout. This is called by `upgrade` once the new
+    //code is deployed, or by the owner directly.
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let state = VersionedContract::read();
+        let predecessor_id = env::predecessor_account_id();
+        assert!(
+            predecessor_id == env::current_account_id() || predecessor_id == state.owner_id(),
+            "Only the owner can call this method"
+        );
+        state.into()
+    }
+
+    //deploy the wasm passed as the raw input of the call and migrate the state to it.
+    //only the owner can call this.
+    #[payable]
+    pub fn upgrade(&mut self) -> Promise {
+        //make sure the owner attaches exactly 1 yoctoNEAR for security purposes.
+        assert_one_yocto();
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.owner_id,
+            "Only the owner can call this method"
+        );
+
+        //the code is the raw input, which avoids encoding the wasm as JSON
+        let code = env::input().unwrap_or_else(|| env::panic_str("The code is missing"));
+        let migrate_gas =
+            env::prepaid_gas().0.saturating_sub(env::used_gas().0 + GAS_FOR_UPGRADE.0);
+        require!(
+            migrate_gas >= MIN_GAS_FOR_MIGRATE.0,
+            "Not enough gas attached to migrate the state"
+        );
+        Promise::new(env::current_account_id())
+            .deploy_contract(code)
+            .function_call(
+                "migrate".to_string(),
+                Vec::new(),
+                NO_DEPOSIT,
+                Gas(migrate_gas),
+            )
+    }
+}
@@ -11,6 +11,9 @@ use near_sdk::{
 // use near_sdk::PromiseResult;
 use near_contract_standards::non_fungible_token::metadata::TokenMetadata;
 // NonFungibleTokenMetadataProvider NFT_METADATA_SPEC Gas
+
+mod upgrade;
+
 near_sdk::setup_alloc!();
 
 // const NFT_WASM_CODE: &[u8] = include_bytes!("./nft-contract/nft_simple.wasm");
@@ -74,6 +77,8 @@ pub struct TokenFactory {
     pub tokens_by_position: UnorderedMap<String, TokenArgs>,
     pub ft_contract_id: AccountId,
     pub nft_contract_id: AccountId,
+    /// Account allowed to upgrade the factory, the one that initialized it.
+    pub owner_id: AccountId,
 }
 
 #[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
@@ -131,6 +136,7 @@ impl TokenFactory {
             tokens_by_position: UnorderedMap::new(StorageKey::TokensByPosition),
             ft_contract_id,
             nft_contract_id,
+            owner_id: env::predecessor_account_id(),
         }
     }
 
@@ -300,3 +306,81 @@ impl TokenFactory {
     
     // --------------------------------------------------------------------------------------------
 }
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::json_types::ValidAccountId;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk::MockedBlockchain;
+
+    use super::*;
+    use crate::upgrade::TokenFactoryV1;
+
+    fn get_context(predecessor_account_id: ValidAccountId) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .signer_account_id(predecessor_account_id.clone())
+            .predecessor_account_id(predecessor_account_id);
+        builder
+    }
+
+    fn sample_token_args() -> TokenArgs {
+        TokenArgs {
+            owner_id: accounts(1).to_string(),
+            token_metadata: TokenMetadata {
+                title: None,
+                description: None,
+                media: None,
+                media_hash: None,
+                copies: None,
+                issued_at: None,
+                expires_at: None,
+                starts_at: None,
+                updated_at: None,
+                extra: None,
+                reference: None,
+                reference_hash: None,
+            },
+            x: "1".to_string(),
+            y: "2".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_migrate_from_v1() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut tokens = UnorderedMap::new(StorageKey::Tokens);
+        tokens.insert(&"R1".to_string(), &sample_token_args());
+        env::state_write(&TokenFactoryV1 {
+            tokens,
+            storage_deposits: LookupMap::new(StorageKey::StorageDeposits),
+            storage_balance_cost: 100,
+            price_thresholds: vec![PriceThreshold { threshold: 1000, price: 700 }],
+            tokens_by_position: UnorderedMap::new(StorageKey::TokensByPosition),
+            ft_contract_id: accounts(2).to_string(),
+            nft_contract_id: accounts(3).to_string(),
+        });
+
+        let factory = TokenFactory::migrate();
+        assert_eq!(factory.get_number_of_tokens(), 1);
+        assert_eq!(factory.get_token("R1".to_string()).unwrap().owner_id, accounts(1).to_string());
+        assert_eq!(factory.storage_balance_cost, 100);
+        assert_eq!(factory.nft_contract_id, accounts(3).to_string());
+        assert_eq!(factory.get_owner(), accounts(0).to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_migrate_not_owner() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let factory = TokenFactory::new(accounts(2).to_string(), accounts(3).to_string());
+        env::state_write(&factory);
+
+        testing_env!(get_context(accounts(1)).build());
+        TokenFactory::migrate();
+    }
+}
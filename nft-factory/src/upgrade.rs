@@ -0,0 +1,114 @@
+//! Upgrades of the factory code. The first factories were deployed without an owner: `migrate`
+//! reads them as `TokenFactoryV1` and makes the factory account the owner.
+use crate::*;
+use near_sdk::{assert_one_yocto, Gas};
+
+/// Gas kept by `upgrade` for itself, the rest of the prepaid gas goes to `migrate`.
+const GAS_FOR_UPGRADE: Gas = 10 * TGAS;
+
+/// Least gas left to `migrate`. With less, `upgrade` fails before anything is deployed.
+const MIN_GAS_FOR_MIGRATE: Gas = 20 * TGAS;
+
+const STATE_KEY: &[u8] = b"STATE";
+
+/// Layout of the first deployments, before the owner was added.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct TokenFactoryV1 {
+    pub tokens: UnorderedMap<TokenId, TokenArgs>,
+    pub storage_deposits: LookupMap<AccountId, Balance>,
+    pub storage_balance_cost: Balance,
+    pub price_thresholds: Vec<PriceThreshold>,
+    pub tokens_by_position: UnorderedMap<String, TokenArgs>,
+    pub ft_contract_id: AccountId,
+    pub nft_contract_id: AccountId,
+}
+
+pub enum VersionedTokenFactory {
+    V1(TokenFactoryV1),
+    Current(TokenFactory),
+}
+
+impl VersionedTokenFactory {
+    /// Reads the stored state, trying the layouts from the newest to the oldest.
+    fn read() -> Self {
+        let state = env::storage_read(STATE_KEY)
+            .unwrap_or_else(|| env::panic(b"The contract is not initialized"));
+        if let Ok(factory) = TokenFactory::try_from_slice(&state) {
+            return Self::Current(factory);
+        }
+        if let Ok(factory) = TokenFactoryV1::try_from_slice(&state) {
+            return Self::V1(factory);
+        }
+        env::panic(b"The state has an unknown layout")
+    }
+
+    /// Account allowed to migrate the state. Layouts without an owner are migrated by the
+    /// factory account itself, which then becomes the owner.
+    fn owner_id(&self) -> AccountId {
+        match self {
+            Self::V1(_) => env::current_account_id(),
+            Self::Current(factory) => factory.owner_id.clone(),
+        }
+    }
+}
+
+impl From<VersionedTokenFactory> for TokenFactory {
+    fn from(state: VersionedTokenFactory) -> Self {
+        match state {
+            VersionedTokenFactory::V1(factory) => TokenFactory {
+                tokens: factory.tokens,
+                storage_deposits: factory.storage_deposits,
+                storage_balance_cost: factory.storage_balance_cost,
+                price_thresholds: factory.price_thresholds,
+                tokens_by_position: factory.tokens_by_position,
+                ft_contract_id: factory.ft_contract_id,
+                nft_contract_id: factory.nft_contract_id,
+                owner_id: env::current_account_id(),
+            },
+            VersionedTokenFactory::Current(factory) => factory,
+        }
+    }
+}
+
+#[near_bindgen]
+impl TokenFactory {
+    /// Rewrites the stored state in the current layout. Called by `upgrade` after deploying new
+    /// code, or by the owner directly.
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let state = VersionedTokenFactory::read();
+        let predecessor_id = env::predecessor_account_id();
+        assert!(
+            predecessor_id == env::current_account_id() || predecessor_id == state.owner_id(),
+            "Only the owner can call this method"
+        );
+        state.into()
+    }
+
+    /// Deploys the wasm passed as the raw input of the call and migrates the state to it. Only
+    /// the owner can call this.
+    #[payable]
+    pub fn upgrade(&mut self) -> Promise {
+        assert_one_yocto();
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.owner_id,
+            "Only the owner can call this method"
+        );
+        let code = env::input().unwrap_or_else(|| env::panic(b"The code is missing"));
+        let migrate_gas = env::prepaid_gas().saturating_sub(env::used_gas() + GAS_FOR_UPGRADE);
+        assert!(migrate_gas >= MIN_GAS_FOR_MIGRATE, "Not enough gas attached to migrate the state");
+        Promise::new(env::current_account_id())
+            .deploy_contract(code)
+            .function_call(
+                b"migrate".to_vec(),
+                Vec::new(),
+                0,
+                migrate_gas,
+            )
+    }
+
+    pub fn get_owner(&self) -> AccountId {
+        self.owner_id.clone()
+    }
+}
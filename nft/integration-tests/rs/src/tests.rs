@@ -6,6 +6,7 @@ use workspaces::{network::Sandbox, Account, Contract, Worker};
 const NFT_WASM_FILEPATH: &str = "../../res/non_fungible_token.wasm";
 const TR_WASM_FILEPATH: &str = "../../res/token_receiver.wasm";
 const AR_WASM_FILEPATH: &str = "../../res/approval_receiver.wasm";
+const MARKET_WASM_FILEPATH: &str = "../../../marketplace/res/nft_simple.wasm";
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -19,6 +20,8 @@ async fn main() -> anyhow::Result<()> {
     let tr_contract = worker.dev_deploy(&tr_wasm).await?;
     let ar_wasm = std::fs::read(AR_WASM_FILEPATH)?;
     let ar_contract = worker.dev_deploy(&ar_wasm).await?;
+    let market_wasm = std::fs::read(MARKET_WASM_FILEPATH)?;
+    let market_contract = worker.dev_deploy(&market_wasm).await?;
 
     // create accounts
     let owner = worker.root_account();
@@ -51,6 +54,13 @@ async fn main() -> anyhow::Result<()> {
         }))?
         .transact()
         .await?;
+    market_contract
+        .call(&worker, "new")
+        .args_json(serde_json::json!({
+            "owner_id": owner.id()
+        }))?
+        .transact()
+        .await?;
 
     // begin tests
     test_simple_approve(&owner, &alice, &nft_contract, &worker).await?;
@@ -68,6 +78,9 @@ async fn main() -> anyhow::Result<()> {
     test_enum_nft_tokens(&nft_contract, &worker).await?;
     test_enum_nft_supply_for_owner(&owner, &alice, &nft_contract, &worker).await?;
     test_enum_nft_tokens_for_owner(&owner, &alice, &nft_contract, &worker).await?;
+    test_marketplace_sale_pays_royalties(&owner, &alice, &nft_contract, &market_contract, &worker)
+        .await?;
+    test_burn(&owner, &nft_contract, &worker).await?;
     Ok(())
 }
 
@@ -727,3 +740,177 @@ async fn test_enum_nft_tokens_for_owner(
     println!("      Passed ✅ test_enum_nft_tokens_for_owner");
     Ok(())
 }
+
+async fn test_marketplace_sale_pays_royalties(
+    owner: &Account,
+    seller: &Account,
+    nft_contract: &Contract,
+    market_contract: &Contract,
+    worker: &Worker<Sandbox>,
+) -> anyhow::Result<()> {
+    use serde_json::Value::String;
+    let buyer = owner
+        .create_subaccount(&worker, "buyer")
+        .initial_balance(parse_near!("30 N"))
+        .transact()
+        .await?
+        .into_result()?;
+    let artist = owner
+        .create_subaccount(&worker, "artist")
+        .initial_balance(parse_near!("5 N"))
+        .transact()
+        .await?
+        .into_result()?;
+
+    // 10% of every sale goes to the artist
+    owner
+        .call(&worker, nft_contract.id(), "nft_mint")
+        .args_json(json!({
+            "token_id": "5",
+            "receiver_id": seller.id(),
+            "token_metadata": {
+                "title": "Olympus Mons 6",
+                "description": "The tallest mountain in the charted solar system",
+                "copies": 1,
+            },
+            "royalty": {
+                artist.id().to_string(): 1_000,
+            },
+        }))?
+        .deposit(parse_near!("0.1 N"))
+        .transact()
+        .await?;
+
+    let payout: serde_json::Value = nft_contract
+        .call(&worker, "nft_payout")
+        .args_json(json!({
+            "token_id": "5",
+            "balance": parse_near!("1 N").to_string(),
+            "max_len_payout": 10,
+        }))?
+        .transact()
+        .await?
+        .json()?;
+    assert_eq!(
+        payout["payout"][artist.id().to_string()],
+        String(parse_near!("0.1 N").to_string())
+    );
+    assert_eq!(
+        payout["payout"][seller.id().to_string()],
+        String(parse_near!("0.9 N").to_string())
+    );
+
+    // the seller lists the token by approving the market
+    seller
+        .call(&worker, market_contract.id(), "storage_deposit")
+        .args_json(json!({}))?
+        .deposit(parse_near!("0.1 N"))
+        .transact()
+        .await?;
+    seller
+        .call(&worker, nft_contract.id(), "nft_approve")
+        .args_json(json!({
+            "token_id": "5",
+            "account_id": market_contract.id(),
+            "msg": json!({"sale_conditions": parse_near!("1 N").to_string()}).to_string(),
+        }))?
+        .deposit(parse_near!("0.01 N"))
+        .gas(parse_gas!("150 Tgas") as u64)
+        .transact()
+        .await?;
+
+    let artist_balance = artist.view_account(&worker).await?.balance;
+    buyer
+        .call(&worker, market_contract.id(), "offer")
+        .args_json(json!({
+            "nft_contract_id": nft_contract.id(),
+            "token_id": "5",
+        }))?
+        .deposit(parse_near!("1 N"))
+        .gas(parse_gas!("300 Tgas") as u64)
+        .transact()
+        .await?;
+
+    let token: serde_json::Value = nft_contract
+        .call(&worker, "nft_token")
+        .args_json(json!({"token_id": "5"}))?
+        .transact()
+        .await?
+        .json()?;
+    assert_eq!(token.get("owner_id"), Some(&String(buyer.id().to_string())));
+    assert_eq!(
+        artist.view_account(&worker).await?.balance,
+        artist_balance + parse_near!("0.1 N")
+    );
+
+    println!("      Passed ✅ test_marketplace_sale_pays_royalties");
+    Ok(())
+}
+
+async fn test_burn(
+    owner: &Account,
+    nft_contract: &Contract,
+    worker: &Worker<Sandbox>,
+) -> anyhow::Result<()> {
+    owner
+        .call(&worker, nft_contract.id(), "nft_mint")
+        .args_json(json!({
+            "token_id": "6",
+            "receiver_id": owner.id(),
+            "token_metadata": {
+                "title": "Olympus Mons 7",
+                "description": "The tallest mountain in the charted solar system",
+                "copies": 1,
+            },
+        }))?
+        .deposit(parse_near!("0.1 N"))
+        .transact()
+        .await?;
+
+    let supply: String = nft_contract
+        .call(&worker, "nft_total_supply")
+        .args_json(json!({}))?
+        .transact()
+        .await?
+        .json()?;
+    assert_eq!(supply, "7");
+    let tokens: Vec<serde_json::Value> = nft_contract
+        .call(&worker, "nft_tokens_for_owner")
+        .args_json(json!({"account_id": owner.id()}))?
+        .transact()
+        .await?
+        .json()?;
+    assert_eq!(tokens.len(), 2);
+
+    owner
+        .call(&worker, nft_contract.id(), "nft_burn")
+        .args_json(json!({"token_id": "6"}))?
+        .deposit(1)
+        .transact()
+        .await?;
+
+    let token: serde_json::Value = nft_contract
+        .call(&worker, "nft_token")
+        .args_json(json!({"token_id": "6"}))?
+        .transact()
+        .await?
+        .json()?;
+    assert!(token.is_null());
+    let supply: String = nft_contract
+        .call(&worker, "nft_total_supply")
+        .args_json(json!({}))?
+        .transact()
+        .await?
+        .json()?;
+    assert_eq!(supply, "6");
+    let tokens: Vec<serde_json::Value> = nft_contract
+        .call(&worker, "nft_tokens_for_owner")
+        .args_json(json!({"account_id": owner.id()}))?
+        .transact()
+        .await?
+        .json()?;
+    assert_eq!(tokens.len(), 1);
+
+    println!("      Passed ✅ test_burn");
+    Ok(())
+}
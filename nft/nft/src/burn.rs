@@ -0,0 +1,114 @@
+//! Burning.
+//!
+//! `near-contract-standards` 3.1 has no way to destroy a token, so the contract removes burned
+//! tokens from every collection itself and refunds the storage they released to their owner.
+use crate::events::NftBurn;
+use crate::series::series_id_of;
+use crate::*;
+use near_sdk::assert_one_yocto;
+
+#[near_bindgen]
+impl Contract {
+    /// Destroys the token. Only its owner or an account it approved can call this.
+    #[payable]
+    pub fn nft_burn(&mut self, token_id: TokenId) {
+        assert_one_yocto();
+        let owner_id = self
+            .tokens
+            .owner_by_id
+            .get(&token_id)
+            .unwrap_or_else(|| env::panic(b"Token not found"));
+        let predecessor_id = env::predecessor_account_id();
+        let authorized_id = if predecessor_id == owner_id {
+            None
+        } else {
+            assert!(
+                self.is_approved(&token_id, &predecessor_id),
+                "Only the owner or an approved account can burn the token"
+            );
+            Some(predecessor_id)
+        };
+
+        self.internal_burn(&token_id, &owner_id);
+        NftBurn {
+            owner_id: &owner_id,
+            token_ids: &[&token_id],
+            authorized_id: authorized_id.as_ref(),
+            memo: None,
+        }
+        .emit();
+    }
+
+    /// Destroys a non-transferable token, such as a badge that was awarded by mistake, without
+    /// the consent of its owner. Only the owner of the contract or the creator of the token's
+    /// series can call this.
+    #[payable]
+    pub fn nft_revoke_non_transferable(&mut self, token_id: TokenId, memo: Option<String>) {
+        assert_one_yocto();
+        let owner_id = self
+            .tokens
+            .owner_by_id
+            .get(&token_id)
+            .unwrap_or_else(|| env::panic(b"Token not found"));
+        assert!(
+            self.non_transferable.contains(&token_id),
+            "Only non-transferable tokens can be revoked"
+        );
+        let predecessor_id = env::predecessor_account_id();
+        let is_series_creator = series_id_of(&token_id)
+            .and_then(|series_id| self.series.get(&series_id))
+            .map_or(false, |series| series.creator_id == predecessor_id);
+        assert!(
+            predecessor_id == self.tokens.owner_id || is_series_creator,
+            "Only the owner or the creator of the series can revoke the token"
+        );
+
+        self.internal_burn(&token_id, &owner_id);
+        NftBurn {
+            owner_id: &owner_id,
+            token_ids: &[&token_id],
+            authorized_id: Some(&predecessor_id),
+            memo: memo.as_deref(),
+        }
+        .emit();
+    }
+}
+
+impl Contract {
+    /// Removes the token from every collection and refunds the storage it released, approvals
+    /// included, to `owner_id`.
+    pub(crate) fn internal_burn(&mut self, token_id: &TokenId, owner_id: &AccountId) {
+        let initial_storage_usage = env::storage_usage();
+        self.tokens.owner_by_id.remove(token_id);
+        if let Some(token_metadata_by_id) = &mut self.tokens.token_metadata_by_id {
+            token_metadata_by_id.remove(token_id);
+        }
+        if let Some(tokens_per_owner) = &mut self.tokens.tokens_per_owner {
+            if let Some(mut token_ids) = tokens_per_owner.get(owner_id) {
+                token_ids.remove(token_id);
+                if token_ids.is_empty() {
+                    tokens_per_owner.remove(owner_id);
+                } else {
+                    tokens_per_owner.insert(owner_id, &token_ids);
+                }
+            }
+        }
+        if let Some(approvals_by_id) = &mut self.tokens.approvals_by_id {
+            approvals_by_id.remove(token_id);
+        }
+        if let Some(next_approval_id_by_id) = &mut self.tokens.next_approval_id_by_id {
+            next_approval_id_by_id.remove(token_id);
+        }
+        self.royalties.remove(token_id);
+        self.non_transferable.remove(token_id);
+        self.redeemed.remove(token_id);
+        self.users.remove(token_id);
+        self.internal_remove_series_token(token_id);
+
+        let storage_released = initial_storage_usage.saturating_sub(env::storage_usage());
+        if storage_released > 0 {
+            Promise::new(owner_id.clone())
+                .transfer(Balance::from(storage_released) * env::storage_byte_cost());
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! Enumeration methods of [NEP-181].
+//!
+//! Implemented by the contract itself rather than with the `near-contract-standards` macro, so
+//! that they return the same tokens as `nft_token` and `nft_tokens_for_owner` can leave expired
+//! tokens out.
+//!
+//! [NEP-181]: https://nomicon.io/Standards/Tokens/NonFungibleToken/Enumeration
+use crate::token::JsonToken;
+use crate::validity::Validity;
+use crate::*;
+use near_sdk::json_types::U128;
+
+#[near_bindgen]
+impl Contract {
+    pub fn nft_total_supply(&self) -> U128 {
+        U128(u128::from(self.tokens.owner_by_id.len()))
+    }
+
+    pub fn nft_tokens(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<JsonToken> {
+        let start_index = u128::from(from_index.unwrap_or(U128(0)));
+        assert!(
+            u128::from(self.tokens.owner_by_id.len()) > start_index,
+            "Out of bounds, please use a smaller from_index."
+        );
+        let limit = limit.map(|limit| limit as usize).unwrap_or(usize::MAX);
+        assert_ne!(limit, 0, "Cannot provide limit of 0.");
+        self.tokens
+            .owner_by_id
+            .iter()
+            .skip(start_index as usize)
+            .take(limit)
+            .filter_map(|(token_id, _)| self.internal_token(&token_id))
+            .collect()
+    }
+
+    pub fn nft_supply_for_owner(&self, account_id: ValidAccountId) -> U128 {
+        let tokens_per_owner = self.internal_tokens_per_owner();
+        U128(
+            tokens_per_owner
+                .get(account_id.as_ref())
+                .map_or(0, |token_ids| u128::from(token_ids.len())),
+        )
+    }
+
+    /// Returns the tokens of `account_id`, leaving out the ones past their `expires_at` if
+    /// `exclude_expired` is `true`. `from_index` then counts the tokens left, and a page past
+    /// the last of them is empty rather than out of bounds.
+    pub fn nft_tokens_for_owner(
+        &self,
+        account_id: ValidAccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+        exclude_expired: Option<bool>,
+    ) -> Vec<JsonToken> {
+        let token_ids = match self.internal_tokens_per_owner().get(account_id.as_ref()) {
+            Some(token_ids) => token_ids,
+            None => return vec![],
+        };
+        let start_index = u128::from(from_index.unwrap_or(U128(0)));
+        let exclude_expired = exclude_expired.unwrap_or(false);
+        // Without the filter the bounds are known upfront, as in the standard implementation
+        if !exclude_expired {
+            assert!(
+                u128::from(token_ids.len()) > start_index,
+                "Out of bounds, please use a smaller from_index."
+            );
+        }
+        let limit = limit.map(|limit| limit as usize).unwrap_or(usize::MAX);
+        assert_ne!(limit, 0, "Cannot provide limit of 0.");
+        token_ids
+            .iter()
+            .filter(|token_id| {
+                !exclude_expired || self.internal_validity(token_id) != Validity::Expired
+            })
+            .skip(start_index as usize)
+            .take(limit)
+            .filter_map(|token_id| self.internal_token(&token_id))
+            .collect()
+    }
+}
+
+impl Contract {
+    fn internal_tokens_per_owner(&self) -> &LookupMap<AccountId, UnorderedSet<TokenId>> {
+        self.tokens.tokens_per_owner.as_ref().expect(
+            "Could not find tokens_per_owner when calling a method on the enumeration standard.",
+        )
+    }
+}
@@ -0,0 +1,126 @@
+//! [NEP-171] events that `near-contract-standards` 3.1 doesn't log by itself, and
+//! contract-specific events logged in the same [NEP-297] format.
+//!
+//! [NEP-171]: https://nomicon.io/Standards/Tokens/NonFungibleToken/Event
+//! [NEP-297]: https://nomicon.io/Standards/EventsFormat
+use near_sdk::json_types::U64;
+use near_sdk::serde::Serialize;
+use near_sdk::serde_json::json;
+use near_sdk::{env, AccountId};
+
+const NFT_STANDARD_NAME: &str = "nep171";
+const NFT_STANDARD_VERSION: &str = "1.1.0";
+const EVENT_STANDARD: &str = "realities";
+const EVENT_STANDARD_VERSION: &str = "1.0.0";
+
+fn emit_event<T: Serialize>(standard: &str, version: &str, event: &str, data: &[T]) {
+    let event = json!({
+        "standard": standard,
+        "version": version,
+        "event": event,
+        "data": data,
+    });
+    env::log(format!("EVENT_JSON:{}", event).as_bytes());
+}
+
+fn emit_nft_event<T: Serialize>(event: &str, data: &[T]) {
+    emit_event(NFT_STANDARD_NAME, NFT_STANDARD_VERSION, event, data)
+}
+
+fn emit_contract_event<T: Serialize>(event: &str, data: &[T]) {
+    emit_event(EVENT_STANDARD, EVENT_STANDARD_VERSION, event, data)
+}
+
+/// Data to log when the contract metadata changes.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftContractMetadataUpdate<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<&'a str>,
+}
+
+impl NftContractMetadataUpdate<'_> {
+    pub fn emit(self) {
+        emit_nft_event("contract_metadata_update", &[self])
+    }
+}
+
+/// Data to log when tokens are burned.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftBurn<'a> {
+    pub owner_id: &'a AccountId,
+    pub token_ids: &'a [&'a str],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorized_id: Option<&'a AccountId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<&'a str>,
+}
+
+impl NftBurn<'_> {
+    pub fn emit(self) {
+        emit_nft_event("nft_burn", &[self])
+    }
+}
+
+/// Data to log when a scanner redeems a ticket.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TicketRedeemed<'a> {
+    pub token_id: &'a str,
+    pub owner_id: &'a AccountId,
+    pub scanner_id: &'a AccountId,
+}
+
+impl TicketRedeemed<'_> {
+    pub fn emit(self) {
+        emit_contract_event("ticket_redeemed", &[self])
+    }
+}
+
+/// Data to log when the user of a token changes, with no user once it's cleared by a transfer.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UserUpdated<'a> {
+    pub token_id: &'a str,
+    pub user_id: Option<&'a AccountId>,
+    pub expires: U64,
+}
+
+impl UserUpdated<'_> {
+    pub fn emit(self) {
+        emit_contract_event("user_updated", &[self])
+    }
+}
+
+/// Data to log when the owner grants the minter role.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MinterAdded<'a> {
+    pub account_id: &'a AccountId,
+}
+
+impl MinterAdded<'_> {
+    pub fn emit(self) {
+        emit_contract_event("minter_added", &[self])
+    }
+}
+
+/// Data to log when the owner revokes the minter role.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MinterRemoved<'a> {
+    pub account_id: &'a AccountId,
+}
+
+impl MinterRemoved<'_> {
+    pub fn emit(self) {
+        emit_contract_event("minter_removed", &[self])
+    }
+}
@@ -21,11 +21,29 @@ use near_contract_standards::non_fungible_token::metadata::{
 use near_contract_standards::non_fungible_token::{Token, TokenId};
 use near_contract_standards::non_fungible_token::NonFungibleToken;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::LazyOption;
+use near_sdk::collections::{LazyOption, LookupMap, LookupSet, UnorderedMap, UnorderedSet};
 use near_sdk::json_types::ValidAccountId;
 use near_sdk::{
-    env, near_bindgen, AccountId, BorshStorageKey, PanicOnDefault, Promise, PromiseOrValue,
+    env, near_bindgen, AccountId, Balance, BorshStorageKey, PanicOnDefault, Promise, PromiseOrValue,
 };
+use std::collections::HashMap;
+
+use crate::rental::TokenUser;
+use crate::royalty::Royalty;
+use crate::series::{Series, SeriesId, SERIES_DELIMITER};
+
+mod burn;
+mod enumeration;
+mod events;
+mod metadata;
+mod mint;
+mod rental;
+mod royalty;
+mod series;
+mod ticket;
+mod token;
+mod upgrade;
+mod validity;
 
 near_sdk::setup_alloc!();
 
@@ -34,6 +52,20 @@ near_sdk::setup_alloc!();
 pub struct Contract {
     tokens: NonFungibleToken,
     metadata: LazyOption<NFTContractMetadata>,
+    /// Token -> royalties paid out of its sales, for tokens minted with any.
+    royalties: LookupMap<TokenId, Royalty>,
+    /// Accounts allowed to mint next to the owner.
+    minters: UnorderedSet<AccountId>,
+    /// Series whose editions are minted as `series_id:edition` tokens.
+    series: UnorderedMap<SeriesId, Series>,
+    /// Tokens that can't be transferred nor approved, such as attendance badges.
+    non_transferable: LookupSet<TokenId>,
+    /// Event tickets that were redeemed.
+    redeemed: LookupSet<TokenId>,
+    /// Whether the `starts_at` and `expires_at` of token metadata are enforced.
+    validity_enforced: bool,
+    /// Token -> account it's rented to, until when.
+    users: LookupMap<TokenId, TokenUser>,
 }
 
 const DATA_IMAGE_SVG_NEAR_ICON: &str = "data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 288 288'%3E%3Cg id='l' data-name='l'%3E%3Cpath d='M187.58,79.81l-30.1,44.69a3.2,3.2,0,0,0,4.75,4.2L191.86,103a1.2,1.2,0,0,1,2,.91v80.46a1.2,1.2,0,0,1-2.12.77L102.18,77.93A15.35,15.35,0,0,0,90.47,72.5H87.34A15.34,15.34,0,0,0,72,87.84V201.16A15.34,15.34,0,0,0,87.34,216.5h0a15.35,15.35,0,0,0,13.08-7.31l30.1-44.69a3.2,3.2,0,0,0-4.75-4.2L96.14,186a1.2,1.2,0,0,1-2-.91V104.61a1.2,1.2,0,0,1,2.12-.77l89.55,107.23a15.35,15.35,0,0,0,11.71,5.43h3.13A15.34,15.34,0,0,0,216,201.16V87.84A15.34,15.34,0,0,0,200.66,72.5h0A15.35,15.35,0,0,0,187.58,79.81Z'/%3E%3C/g%3E%3C/svg%3E";
@@ -45,6 +77,15 @@ enum StorageKey {
     TokenMetadata,
     Enumeration,
     Approval,
+    Royalties,
+    TokensPerOwner { account_hash: Vec<u8> },
+    Minters,
+    Series,
+    SeriesTokens { series_id: SeriesId },
+    NonTransferable,
+    Redeemed,
+    SeriesScanners { series_id: SeriesId },
+    Users,
 }
 
 #[near_bindgen]
@@ -80,31 +121,100 @@ impl Contract {
                 Some(StorageKey::Approval),
             ),
             metadata: LazyOption::new(StorageKey::Metadata, Some(&metadata)),
+            royalties: LookupMap::new(StorageKey::Royalties),
+            minters: UnorderedSet::new(StorageKey::Minters),
+            series: UnorderedMap::new(StorageKey::Series),
+            non_transferable: LookupSet::new(StorageKey::NonTransferable),
+            redeemed: LookupSet::new(StorageKey::Redeemed),
+            validity_enforced: false,
+            users: LookupMap::new(StorageKey::Users),
         }
     }
 
     /// Mint a new token with ID=`token_id` belonging to `receiver_id`.
     ///
     /// Since this example implements metadata, it also requires per-token metadata to be provided
-    /// in this call. `self.tokens.mint` will also require it to be Some, since
-    /// `StorageKey::TokenMetadata` was provided at initialization.
+    /// in this call. `royalty` maps accounts to their share of every sale of the token in basis
+    /// points, and can't add up to more than 100%. The token can't be transferred nor approved if
+    /// `transferable` is `false`.
     ///
-    /// `self.tokens.mint` will enforce `predecessor_account_id` to equal the `owner_id` given in
-    /// initialization call to `new`.
+    /// Only the `owner_id` given in initialization call to `new` and the minters it adds can mint.
     #[payable]
     pub fn nft_mint(
         &mut self,
         token_id: TokenId,
         receiver_id: ValidAccountId,
         token_metadata: TokenMetadata,
+        royalty: Option<Royalty>,
+        transferable: Option<bool>,
     ) -> Token {
-        self.tokens.mint(token_id, receiver_id, Some(token_metadata))
+        self.assert_minter();
+        assert!(
+            !token_id.contains(SERIES_DELIMITER),
+            "Only series tokens can contain ':'"
+        );
+        let initial_storage_usage = env::storage_usage();
+        let token = self.internal_mint(
+            token_id,
+            receiver_id.into(),
+            token_metadata,
+            royalty.unwrap_or_default(),
+            transferable.unwrap_or(true),
+        );
+        refund_deposit(env::storage_usage() - initial_storage_usage, 0);
+        token
+    }
+}
+
+impl Contract {
+    pub(crate) fn assert_owner(&self) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.tokens.owner_id,
+            "Only the owner can call this method"
+        );
+    }
+}
+
+/// Charges the caller for `storage_used` bytes and `price` out of the attached deposit and refunds
+/// the rest.
+pub(crate) fn refund_deposit(storage_used: u64, price: Balance) {
+    let required_cost = env::storage_byte_cost() * Balance::from(storage_used) + price;
+    let attached_deposit = env::attached_deposit();
+    if price > 0 {
+        assert!(
+            required_cost <= attached_deposit,
+            "Must attach {} yoctoNEAR to cover the price and storage",
+            required_cost
+        );
+    } else {
+        assert!(
+            required_cost <= attached_deposit,
+            "Must attach {} yoctoNEAR to cover storage",
+            required_cost
+        );
+    }
+    let refund = attached_deposit - required_cost;
+    if refund > 1 {
+        Promise::new(env::predecessor_account_id()).transfer(refund);
     }
 }
 
-near_contract_standards::impl_non_fungible_token_core!(Contract, tokens);
-near_contract_standards::impl_non_fungible_token_approval!(Contract, tokens);
-near_contract_standards::impl_non_fungible_token_enumeration!(Contract, tokens);
+/// Refunds the storage of the approvals a token had to `account_id`, like `nft_transfer` does.
+pub(crate) fn refund_approved_account_ids(
+    account_id: AccountId,
+    approved_account_ids: &HashMap<AccountId, u64>,
+) {
+    // Each approval stores the account id with its length, and the approval id
+    let storage_released: u64 = approved_account_ids
+        .keys()
+        .map(|account_id| account_id.len() as u64 + 4 + std::mem::size_of::<u64>() as u64)
+        .sum();
+    if storage_released > 0 {
+        Promise::new(account_id)
+            .transfer(Balance::from(storage_released) * env::storage_byte_cost());
+    }
+}
 
 #[near_bindgen]
 impl NonFungibleTokenMetadataProvider for Contract {
@@ -115,11 +225,14 @@ impl NonFungibleTokenMetadataProvider for Contract {
 
 #[cfg(all(test, not(target_arch = "wasm32")))]
 mod tests {
-    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
     use near_sdk::testing_env;
     use near_sdk::MockedBlockchain;
 
     use super::*;
+    use crate::upgrade::{ContractV1, ContractV4, ContractV5, SeriesV1, SeriesV2};
+    use near_sdk::json_types::{U128, U64};
+    use std::iter::FromIterator;
 
     const MINT_STORAGE_COST: u128 = 5870000000000000000000;
 
@@ -132,6 +245,18 @@ mod tests {
         builder
     }
 
+    fn sample_contract_metadata() -> NFTContractMetadata {
+        NFTContractMetadata {
+            spec: NFT_METADATA_SPEC.to_string(),
+            name: "Realities".to_string(),
+            symbol: "RLTS".to_string(),
+            icon: None,
+            base_uri: None,
+            reference: None,
+            reference_hash: None,
+        }
+    }
+
     fn sample_token_metadata() -> TokenMetadata {
         TokenMetadata {
             title: Some("Olympus Mons".into()),
@@ -179,7 +304,13 @@ mod tests {
             .build());
 
         let token_id = "0".to_string();
-        let token = contract.nft_mint(token_id.clone(), accounts(0), sample_token_metadata());
+        let token = contract.nft_mint(
+            token_id.clone(),
+            accounts(0),
+            sample_token_metadata(),
+            None,
+            None,
+        );
         assert_eq!(token.token_id, token_id);
         assert_eq!(token.owner_id, accounts(0).to_string());
         assert_eq!(token.metadata.unwrap(), sample_token_metadata());
@@ -198,7 +329,13 @@ mod tests {
             .predecessor_account_id(accounts(0))
             .build());
         let token_id = "0".to_string();
-        contract.nft_mint(token_id.clone(), accounts(0), sample_token_metadata());
+        contract.nft_mint(
+            token_id.clone(),
+            accounts(0),
+            sample_token_metadata(),
+            None,
+            None,
+        );
 
         testing_env!(context
             .storage_usage(env::storage_usage())
@@ -235,7 +372,13 @@ mod tests {
             .predecessor_account_id(accounts(0))
             .build());
         let token_id = "0".to_string();
-        contract.nft_mint(token_id.clone(), accounts(0), sample_token_metadata());
+        contract.nft_mint(
+            token_id.clone(),
+            accounts(0),
+            sample_token_metadata(),
+            None,
+            None,
+        );
 
         // alice approves bob
         testing_env!(context
@@ -266,7 +409,13 @@ mod tests {
             .predecessor_account_id(accounts(0))
             .build());
         let token_id = "0".to_string();
-        contract.nft_mint(token_id.clone(), accounts(0), sample_token_metadata());
+        contract.nft_mint(
+            token_id.clone(),
+            accounts(0),
+            sample_token_metadata(),
+            None,
+            None,
+        );
 
         // alice approves bob
         testing_env!(context
@@ -304,7 +453,13 @@ mod tests {
             .predecessor_account_id(accounts(0))
             .build());
         let token_id = "0".to_string();
-        contract.nft_mint(token_id.clone(), accounts(0), sample_token_metadata());
+        contract.nft_mint(
+            token_id.clone(),
+            accounts(0),
+            sample_token_metadata(),
+            None,
+            None,
+        );
 
         // alice approves bob
         testing_env!(context
@@ -329,4 +484,1058 @@ mod tests {
             .build());
         assert!(!contract.nft_is_approved(token_id.clone(), accounts(1), Some(1)));
     }
+
+    #[test]
+    fn test_update_metadata() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0).into());
+
+        testing_env!(context
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(0))
+            .build());
+        contract.set_name("Realities".to_string());
+        contract.set_base_uri(Some("https://ipfs.io/ipfs".to_string()));
+        let metadata = contract.nft_metadata();
+        assert_eq!(metadata.name, "Realities");
+        assert_eq!(metadata.base_uri, Some("https://ipfs.io/ipfs".to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_update_metadata_not_owner() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0).into());
+
+        testing_env!(context
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.set_symbol("RLTS".to_string());
+    }
+
+    #[test]
+    fn test_migrate() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(1).into());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST)
+            .predecessor_account_id(accounts(1))
+            .build());
+        let token_id = "0".to_string();
+        contract.nft_mint(
+            token_id.clone(),
+            accounts(2),
+            sample_token_metadata(),
+            None,
+            None,
+        );
+        env::state_write(&contract);
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let contract = Contract::migrate();
+        let token = contract.nft_token(token_id).unwrap();
+        assert_eq!(token.owner_id, accounts(2).to_string());
+        assert_eq!(token.metadata.unwrap(), sample_token_metadata());
+        assert_eq!(contract.tokens.owner_id, accounts(1).to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_migrate_not_owner() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let contract = Contract::new_default_meta(accounts(1).into());
+        env::state_write(&contract);
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        Contract::migrate();
+    }
+
+    /// Tokens owned by `accounts(1)`, as stored by every layout.
+    fn empty_tokens() -> NonFungibleToken {
+        NonFungibleToken::new(
+            StorageKey::NonFungibleToken,
+            accounts(1),
+            Some(StorageKey::TokenMetadata),
+            Some(StorageKey::Enumeration),
+            Some(StorageKey::Approval),
+        )
+    }
+
+    #[test]
+    fn test_migrate_from_v1() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        env::state_write(&ContractV1 {
+            tokens: empty_tokens(),
+            metadata: LazyOption::new(StorageKey::Metadata, Some(&sample_contract_metadata())),
+        });
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::migrate();
+        assert_eq!(contract.nft_metadata().name, "Realities");
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST * 2)
+            .build());
+        let token_id = "0".to_string();
+        let royalty = HashMap::from_iter(vec![(accounts(3).to_string(), 500)]);
+        contract.nft_mint(
+            token_id.clone(),
+            accounts(2),
+            sample_token_metadata(),
+            Some(royalty),
+            None,
+        );
+        let payout = contract.nft_payout(token_id, U128(1_000), 2).payout;
+        assert_eq!(payout[&accounts(3).to_string()], U128(50));
+    }
+
+    #[test]
+    fn test_migrate_from_v4() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut minters = UnorderedSet::new(StorageKey::Minters);
+        minters.insert(&accounts(2).to_string());
+        let mut series = UnorderedMap::new(StorageKey::Series);
+        series.insert(
+            &1,
+            &SeriesV1 {
+                creator_id: accounts(2).to_string(),
+                metadata: sample_token_metadata(),
+                price: Some(1_000),
+                royalty: HashMap::new(),
+                minted: 0,
+                tokens: UnorderedSet::new(StorageKey::SeriesTokens { series_id: 1 }),
+            },
+        );
+        env::state_write(&ContractV4 {
+            tokens: empty_tokens(),
+            metadata: LazyOption::new(StorageKey::Metadata, Some(&sample_contract_metadata())),
+            royalties: LookupMap::new(StorageKey::Royalties),
+            minters,
+            series,
+        });
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::migrate();
+        assert!(contract.is_minter(accounts(2)));
+        let series = contract.nft_get_series(1).unwrap();
+        assert_eq!(series.creator_id, accounts(2).to_string());
+        assert_eq!(series.price, Some(U128(1_000)));
+        assert!(series.transferable);
+        assert!(contract.get_scanners(1, None, None).is_empty());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST * 2)
+            .predecessor_account_id(accounts(2))
+            .build());
+        let token = contract.nft_mint_series(1, accounts(3));
+        assert_eq!(token.token_id, "1:1");
+        assert_eq!(contract.nft_supply_for_series(1), U128(1));
+    }
+
+    #[test]
+    fn test_migrate_from_v5() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut series = UnorderedMap::new(StorageKey::Series);
+        series.insert(
+            &1,
+            &SeriesV2 {
+                creator_id: accounts(1).to_string(),
+                metadata: sample_token_metadata(),
+                price: None,
+                royalty: HashMap::new(),
+                transferable: false,
+                minted: 0,
+                tokens: UnorderedSet::new(StorageKey::SeriesTokens { series_id: 1 }),
+            },
+        );
+        let mut non_transferable = LookupSet::new(StorageKey::NonTransferable);
+        non_transferable.insert(&"badge".to_string());
+        env::state_write(&ContractV5 {
+            tokens: empty_tokens(),
+            metadata: LazyOption::new(StorageKey::Metadata, Some(&sample_contract_metadata())),
+            royalties: LookupMap::new(StorageKey::Royalties),
+            minters: UnorderedSet::new(StorageKey::Minters),
+            series,
+            non_transferable,
+        });
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let contract = Contract::migrate();
+        let series = contract.nft_get_series(1).unwrap();
+        assert!(!series.transferable);
+        assert!(!series.redeem_within_validity);
+        assert!(contract.non_transferable.contains(&"badge".to_string()));
+    }
+
+    #[test]
+    fn test_transfer_payout() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0).into());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST * 2)
+            .predecessor_account_id(accounts(0))
+            .build());
+        let token_id = "0".to_string();
+        let royalty = HashMap::from_iter(vec![
+            (accounts(0).to_string(), 250),
+            (accounts(3).to_string(), 1_000),
+        ]);
+        contract.nft_mint(
+            token_id.clone(),
+            accounts(1),
+            sample_token_metadata(),
+            Some(royalty),
+            None,
+        );
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(1))
+            .build());
+        let payout = contract
+            .nft_transfer_payout(accounts(2), token_id.clone(), None, None, U128(10_001), 3)
+            .payout;
+        assert_eq!(payout.len(), 3);
+        assert_eq!(payout[&accounts(0).to_string()], U128(250));
+        assert_eq!(payout[&accounts(3).to_string()], U128(1_000));
+        assert_eq!(payout[&accounts(1).to_string()], U128(8_751));
+        assert_eq!(
+            contract.nft_token(token_id).unwrap().owner_id,
+            accounts(2).to_string()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "more than max_len_payout")]
+    fn test_payout_above_max_len() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0).into());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST * 2)
+            .predecessor_account_id(accounts(0))
+            .build());
+        let token_id = "0".to_string();
+        let royalty = HashMap::from_iter(vec![(accounts(3).to_string(), 1_000)]);
+        contract.nft_mint(
+            token_id.clone(),
+            accounts(1),
+            sample_token_metadata(),
+            Some(royalty),
+            None,
+        );
+        contract.nft_payout(token_id, U128(1_000), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "The royalties can't exceed 100%")]
+    fn test_mint_royalty_above_total() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0).into());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST * 2)
+            .predecessor_account_id(accounts(0))
+            .build());
+        let royalty = HashMap::from_iter(vec![
+            (accounts(2).to_string(), 6_000),
+            (accounts(3).to_string(), 5_000),
+        ]);
+        contract.nft_mint(
+            "0".to_string(),
+            accounts(1),
+            sample_token_metadata(),
+            Some(royalty),
+            None,
+        );
+    }
+
+    #[test]
+    fn test_minter_mints() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0).into());
+
+        testing_env!(context.attached_deposit(1).build());
+        contract.add_minter(accounts(1));
+        assert!(contract.is_minter(accounts(1)));
+        assert_eq!(
+            contract.get_minters(None, None),
+            vec![accounts(1).to_string()]
+        );
+        assert_eq!(
+            get_logs(),
+            vec![format!(
+                r#"EVENT_JSON:{{"data":[{{"account_id":"{}"}}],"event":"minter_added","standard":"realities","version":"1.0.0"}}"#,
+                accounts(1)
+            )]
+        );
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST)
+            .predecessor_account_id(accounts(1))
+            .build());
+        let token = contract.nft_mint(
+            "0".to_string(),
+            accounts(2),
+            sample_token_metadata(),
+            None,
+            None,
+        );
+        assert_eq!(token.owner_id, accounts(2).to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only minters can call this method")]
+    fn test_mint_not_minter() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0).into());
+
+        testing_env!(context.attached_deposit(1).build());
+        contract.add_minter(accounts(1));
+        contract.remove_minter(accounts(1));
+        assert!(!contract.is_minter(accounts(1)));
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST)
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.nft_mint(
+            "0".to_string(),
+            accounts(2),
+            sample_token_metadata(),
+            None,
+            None,
+        );
+    }
+
+    #[test]
+    fn test_mint_series() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0).into());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST * 2)
+            .build());
+        let mut metadata = sample_token_metadata();
+        metadata.copies = Some(2);
+        let royalty = HashMap::from_iter(vec![(accounts(0).to_string(), 500)]);
+        let series_id =
+            contract.nft_create_series(metadata, Some(U128(1_000)), Some(royalty), None);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST * 2)
+            .build());
+        let token = contract.nft_mint_series(series_id, accounts(1));
+        assert_eq!(token.token_id, "1:1");
+        assert_eq!(
+            token.metadata.unwrap().title,
+            Some("Olympus Mons #1".to_string())
+        );
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST * 2 + 1_000)
+            .predecessor_account_id(accounts(2))
+            .build());
+        let token = contract.nft_mint_series(series_id, accounts(2));
+        assert_eq!(token.token_id, "1:2");
+        assert_eq!(
+            contract
+                .nft_payout(token.token_id, U128(100), 2)
+                .payout
+                .len(),
+            2
+        );
+
+        let series = contract.nft_get_series(series_id).unwrap();
+        assert_eq!(series.creator_id, accounts(0).to_string());
+        assert_eq!(series.minted, 2);
+        assert_eq!(contract.nft_supply_for_series(series_id), U128(2));
+        let token_ids: Vec<TokenId> = contract
+            .nft_tokens_by_series(series_id, None, None)
+            .into_iter()
+            .map(|token| token.token_id)
+            .collect();
+        assert_eq!(token_ids, vec!["1:1".to_string(), "1:2".to_string()]);
+    }
+
+    #[test]
+    #[should_panic(expected = "All the copies of the series are minted")]
+    fn test_mint_series_above_copies() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0).into());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST * 2)
+            .build());
+        let series_id = contract.nft_create_series(sample_token_metadata(), None, None, None);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST)
+            .build());
+        contract.nft_mint_series(series_id, accounts(1));
+        contract.nft_mint_series(series_id, accounts(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "The series isn't for sale")]
+    fn test_mint_series_not_for_sale() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0).into());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST * 2)
+            .build());
+        let series_id = contract.nft_create_series(sample_token_metadata(), None, None, None);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST)
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.nft_mint_series(series_id, accounts(1));
+    }
+
+    #[test]
+    fn test_burn() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0).into());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST * 2)
+            .build());
+        let series_id = contract.nft_create_series(sample_token_metadata(), None, None, None);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST)
+            .build());
+        contract.nft_mint_series(series_id, accounts(1));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST)
+            .build());
+        contract.nft_mint(
+            "0".to_string(),
+            accounts(1),
+            sample_token_metadata(),
+            None,
+            None,
+        );
+        assert_eq!(contract.nft_total_supply(), U128(2));
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.nft_burn("1:1".to_string());
+        assert_eq!(
+            get_logs(),
+            vec![format!(
+                r#"EVENT_JSON:{{"data":[{{"owner_id":"{}","token_ids":["1:1"]}}],"event":"nft_burn","standard":"nep171","version":"1.1.0"}}"#,
+                accounts(1)
+            )]
+        );
+        assert_eq!(contract.nft_token("1:1".to_string()), None);
+        assert_eq!(contract.nft_total_supply(), U128(1));
+        assert_eq!(contract.nft_supply_for_owner(accounts(1)), U128(1));
+        assert_eq!(contract.nft_supply_for_series(series_id), U128(0));
+        assert_eq!(contract.nft_get_series(series_id).unwrap().minted, 1);
+    }
+
+    #[test]
+    fn test_burn_approved() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0).into());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST)
+            .build());
+        let token_id = "0".to_string();
+        contract.nft_mint(
+            token_id.clone(),
+            accounts(0),
+            sample_token_metadata(),
+            None,
+            None,
+        );
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(150000000000000000000)
+            .build());
+        contract.nft_approve(token_id.clone(), accounts(1), None);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.nft_burn(token_id.clone());
+        assert_eq!(contract.nft_token(token_id), None);
+        assert_eq!(contract.nft_total_supply(), U128(0));
+        assert!(contract
+            .nft_tokens_for_owner(accounts(0), None, None, None)
+            .is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner or an approved account can burn the token")]
+    fn test_burn_not_owner() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0).into());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST)
+            .build());
+        contract.nft_mint(
+            "0".to_string(),
+            accounts(0),
+            sample_token_metadata(),
+            None,
+            None,
+        );
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.nft_burn("0".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "The token is non-transferable")]
+    fn test_transfer_not_transferable() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0).into());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST)
+            .build());
+        let token_id = "0".to_string();
+        contract.nft_mint(
+            token_id.clone(),
+            accounts(1),
+            sample_token_metadata(),
+            None,
+            Some(false),
+        );
+        assert!(!contract.nft_token(token_id.clone()).unwrap().transferable);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.nft_transfer(accounts(2), token_id, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The token is non-transferable")]
+    fn test_approve_not_transferable() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0).into());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST)
+            .build());
+        let token_id = "0".to_string();
+        contract.nft_mint(
+            token_id.clone(),
+            accounts(1),
+            sample_token_metadata(),
+            None,
+            Some(false),
+        );
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(150000000000000000000)
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.nft_approve(token_id, accounts(2), None);
+    }
+
+    #[test]
+    fn test_burn_not_transferable() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0).into());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST * 2)
+            .build());
+        let series_id =
+            contract.nft_create_series(sample_token_metadata(), None, None, Some(false));
+        assert!(!contract.nft_get_series(series_id).unwrap().transferable);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST)
+            .build());
+        let token = contract.nft_mint_series(series_id, accounts(1));
+        assert!(
+            !contract
+                .nft_token(token.token_id.clone())
+                .unwrap()
+                .transferable
+        );
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.nft_revoke_all(token.token_id.clone());
+        contract.nft_burn(token.token_id.clone());
+        assert_eq!(contract.nft_token(token.token_id), None);
+    }
+
+    /// `accounts(2)` creates a non-transferable series and mints an edition to `accounts(1)`.
+    fn setup_badge(context: &mut VMContextBuilder) -> (Contract, TokenId) {
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0).into());
+        testing_env!(context.attached_deposit(1).build());
+        contract.add_minter(accounts(2));
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST * 2)
+            .predecessor_account_id(accounts(2))
+            .build());
+        let series_id =
+            contract.nft_create_series(sample_token_metadata(), None, None, Some(false));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST)
+            .build());
+        let token = contract.nft_mint_series(series_id, accounts(1));
+        (contract, token.token_id)
+    }
+
+    #[test]
+    fn test_revoke_non_transferable() {
+        let mut context = get_context(accounts(0));
+        let (mut contract, token_id) = setup_badge(&mut context);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.nft_revoke_non_transferable(token_id.clone(), Some("mistake".to_string()));
+        assert_eq!(contract.nft_token(token_id.clone()), None);
+        assert_eq!(contract.nft_supply_for_owner(accounts(1)), U128(0));
+        assert_eq!(contract.nft_supply_for_series(1), U128(0));
+        assert_eq!(contract.nft_total_supply(), U128(0));
+        assert_eq!(
+            get_logs(),
+            vec![format!(
+                r#"EVENT_JSON:{{"data":[{{"owner_id":"{}","token_ids":["{}"],"authorized_id":"{}","memo":"mistake"}}],"event":"nft_burn","standard":"nep171","version":"1.1.0"}}"#,
+                accounts(1),
+                token_id,
+                accounts(2)
+            )]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner or the creator of the series can revoke the token")]
+    fn test_revoke_non_transferable_not_creator() {
+        let mut context = get_context(accounts(0));
+        let (mut contract, token_id) = setup_badge(&mut context);
+
+        testing_env!(context
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.nft_revoke_non_transferable(token_id, None);
+    }
+
+    /// Creates a series of tickets with `accounts(2)` as scanner and mints one to `accounts(1)`.
+    fn setup_ticket(
+        context: &mut VMContextBuilder,
+        metadata: TokenMetadata,
+        redeem_within_validity: bool,
+    ) -> (Contract, TokenId) {
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0).into());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST * 2)
+            .build());
+        let series_id = contract.nft_create_series(metadata, None, None, None);
+        contract.add_scanner(series_id, accounts(2));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .build());
+        contract.set_ticket_rules(series_id, redeem_within_validity, true);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST)
+            .build());
+        let token = contract.nft_mint_series(series_id, accounts(1));
+        (contract, token.token_id)
+    }
+
+    #[test]
+    fn test_redeem_ticket() {
+        let mut context = get_context(accounts(0));
+        let (mut contract, token_id) = setup_ticket(&mut context, sample_token_metadata(), false);
+        assert_eq!(
+            contract.get_scanners(1, None, None),
+            vec![accounts(2).to_string()]
+        );
+        assert!(!contract.nft_token(token_id.clone()).unwrap().redeemed);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.redeem_ticket(token_id.clone());
+        assert_eq!(
+            get_logs(),
+            vec![format!(
+                r#"EVENT_JSON:{{"data":[{{"owner_id":"{}","scanner_id":"{}","token_id":"{}"}}],"event":"ticket_redeemed","standard":"realities","version":"1.0.0"}}"#,
+                accounts(1),
+                accounts(2),
+                token_id
+            )]
+        );
+        let token = contract.nft_token(token_id).unwrap();
+        assert!(token.redeemed);
+        assert!(!token.transferable);
+    }
+
+    #[test]
+    #[should_panic(expected = "The ticket is already redeemed")]
+    fn test_redeem_ticket_twice() {
+        let mut context = get_context(accounts(0));
+        let (mut contract, token_id) = setup_ticket(&mut context, sample_token_metadata(), false);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST)
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.redeem_ticket(token_id.clone());
+        contract.redeem_ticket(token_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only scanners of the event can redeem its tickets")]
+    fn test_redeem_ticket_not_scanner() {
+        let mut context = get_context(accounts(0));
+        let (mut contract, token_id) = setup_ticket(&mut context, sample_token_metadata(), false);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST)
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.redeem_ticket(token_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "The ticket has expired")]
+    fn test_redeem_ticket_expired() {
+        let mut context = get_context(accounts(0));
+        let mut metadata = sample_token_metadata();
+        metadata.expires_at = Some("1000".to_string());
+        let (mut contract, token_id) = setup_ticket(&mut context, metadata, true);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST)
+            .predecessor_account_id(accounts(2))
+            .block_timestamp(2_000_000_000)
+            .build());
+        contract.redeem_ticket(token_id);
+    }
+
+    /// Mints "0", expired at 1000 ms, and "1", without a validity window, to `accounts(1)`, then
+    /// moves past the expiry.
+    fn setup_expired(context: &mut VMContextBuilder, enforced: bool) -> Contract {
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0).into());
+        testing_env!(context.attached_deposit(1).build());
+        contract.set_validity_enforced(enforced);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST * 2)
+            .build());
+        let mut metadata = sample_token_metadata();
+        metadata.expires_at = Some("1000".to_string());
+        contract.nft_mint("0".to_string(), accounts(1), metadata, None, None);
+        contract.nft_mint(
+            "1".to_string(),
+            accounts(1),
+            sample_token_metadata(),
+            None,
+            None,
+        );
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .block_timestamp(2_000_000_000)
+            .build());
+        contract
+    }
+
+    #[test]
+    #[should_panic(expected = "The token has expired")]
+    fn test_transfer_expired() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_expired(&mut context, true);
+        assert!(contract.is_validity_enforced());
+
+        testing_env!(context
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.nft_transfer(accounts(2), "1".to_string(), None, None);
+        contract.nft_transfer(accounts(2), "0".to_string(), None, None);
+    }
+
+    #[test]
+    fn test_transfer_expired_not_enforced() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_expired(&mut context, false);
+
+        testing_env!(context
+            .attached_deposit(1)
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.nft_transfer(accounts(2), "0".to_string(), None, None);
+        assert_eq!(
+            contract.nft_token("0".to_string()).unwrap().owner_id,
+            accounts(2).to_string()
+        );
+    }
+
+    #[test]
+    fn test_tokens_for_owner_exclude_expired() {
+        let mut context = get_context(accounts(0));
+        let contract = setup_expired(&mut context, false);
+
+        assert_eq!(
+            contract
+                .nft_tokens_for_owner(accounts(1), None, None, None)
+                .len(),
+            2
+        );
+        let token_ids: Vec<TokenId> = contract
+            .nft_tokens_for_owner(accounts(1), None, None, Some(true))
+            .into_iter()
+            .map(|token| token.token_id)
+            .collect();
+        assert_eq!(token_ids, vec!["1".to_string()]);
+        assert!(contract
+            .nft_tokens_for_owner(accounts(1), Some(U128(1)), None, Some(true))
+            .is_empty());
+        assert!(contract
+            .nft_tokens_for_owner(accounts(1), Some(U128(2)), None, Some(true))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_burn_expired() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_expired(&mut context, true);
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.nft_burn_expired("0".to_string());
+        assert_eq!(contract.nft_token("0".to_string()), None);
+        assert_eq!(contract.nft_supply_for_owner(accounts(1)), U128(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "The token hasn't expired")]
+    fn test_burn_not_expired() {
+        let mut context = get_context(accounts(0));
+        let mut contract = setup_expired(&mut context, true);
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.nft_burn_expired("1".to_string());
+    }
+
+    #[test]
+    fn test_set_user() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0).into());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST)
+            .build());
+        let token_id = "0".to_string();
+        contract.nft_mint(
+            token_id.clone(),
+            accounts(1),
+            sample_token_metadata(),
+            None,
+            None,
+        );
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST)
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.nft_set_user(token_id.clone(), accounts(2), U64(1000));
+        assert_eq!(
+            get_logs(),
+            vec![format!(
+                r#"EVENT_JSON:{{"data":[{{"expires":"1000","token_id":"0","user_id":"{}"}}],"event":"user_updated","standard":"realities","version":"1.0.0"}}"#,
+                accounts(2)
+            )]
+        );
+        assert_eq!(
+            contract.nft_user_of(token_id.clone()),
+            Some(accounts(2).to_string())
+        );
+        assert_eq!(contract.nft_user_expires(token_id.clone()), Some(U64(1000)));
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .block_timestamp(1_000_000_000)
+            .build());
+        assert_eq!(contract.nft_user_of(token_id), None);
+    }
+
+    #[test]
+    fn test_transfer_clears_user() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0).into());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST)
+            .build());
+        let token_id = "0".to_string();
+        contract.nft_mint(
+            token_id.clone(),
+            accounts(0),
+            sample_token_metadata(),
+            None,
+            None,
+        );
+        contract.nft_set_user(token_id.clone(), accounts(2), U64(1000));
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .build());
+        contract.nft_transfer(accounts(1), token_id.clone(), None, None);
+        assert_eq!(contract.nft_user_of(token_id), None);
+    }
+
+    #[test]
+    fn test_transfer_call_keeps_user_until_resolved() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0).into());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST)
+            .build());
+        let token_id = "0".to_string();
+        contract.nft_mint(
+            token_id.clone(),
+            accounts(0),
+            sample_token_metadata(),
+            None,
+            None,
+        );
+        contract.nft_set_user(token_id.clone(), accounts(2), U64(1000));
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(1)
+            .build());
+        contract.nft_transfer_call(accounts(1), token_id.clone(), None, None, "".to_string());
+        assert_eq!(
+            contract.nft_user_of(token_id),
+            Some(accounts(2).to_string())
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner or an approved account can set the user")]
+    fn test_set_user_not_owner() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(0).into());
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(MINT_STORAGE_COST)
+            .build());
+        let token_id = "0".to_string();
+        contract.nft_mint(
+            token_id.clone(),
+            accounts(0),
+            sample_token_metadata(),
+            None,
+            None,
+        );
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.nft_set_user(token_id, accounts(2), U64(1000));
+    }
 }
@@ -0,0 +1,62 @@
+//! Owner-managed contract metadata.
+//!
+//! Every update is validated with `assert_valid` and logged as a NEP-171 `contract_metadata_update`
+//! event, so wallets and indexers know to fetch `nft_metadata` again.
+use crate::events::NftContractMetadataUpdate;
+use crate::*;
+use near_sdk::assert_one_yocto;
+use near_sdk::json_types::Base64VecU8;
+
+#[near_bindgen]
+impl Contract {
+    /// Only the owner can call this.
+    #[payable]
+    pub fn set_name(&mut self, name: String) {
+        self.internal_update_metadata(|metadata| metadata.name = name);
+    }
+
+    /// Only the owner can call this.
+    #[payable]
+    pub fn set_symbol(&mut self, symbol: String) {
+        self.internal_update_metadata(|metadata| metadata.symbol = symbol);
+    }
+
+    /// Sets the icon as a data URL, or removes it with `None`. Only the owner can call this.
+    #[payable]
+    pub fn set_icon(&mut self, icon: Option<String>) {
+        self.internal_update_metadata(|metadata| metadata.icon = icon);
+    }
+
+    /// Sets the gateway that relative token media and references are resolved against.
+    /// Only the owner can call this.
+    #[payable]
+    pub fn set_base_uri(&mut self, base_uri: Option<String>) {
+        self.internal_update_metadata(|metadata| metadata.base_uri = base_uri);
+    }
+
+    /// Sets the off-chain reference together with its sha256 hash, or removes both with `None`.
+    /// Only the owner can call this.
+    #[payable]
+    pub fn set_reference(
+        &mut self,
+        reference: Option<String>,
+        reference_hash: Option<Base64VecU8>,
+    ) {
+        self.internal_update_metadata(|metadata| {
+            metadata.reference = reference;
+            metadata.reference_hash = reference_hash;
+        });
+    }
+}
+
+impl Contract {
+    fn internal_update_metadata(&mut self, update: impl FnOnce(&mut NFTContractMetadata)) {
+        assert_one_yocto();
+        self.assert_owner();
+        let mut metadata = self.metadata.get().unwrap();
+        update(&mut metadata);
+        metadata.assert_valid();
+        self.metadata.set(&metadata);
+        NftContractMetadataUpdate { memo: None }.emit();
+    }
+}
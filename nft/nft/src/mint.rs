@@ -0,0 +1,113 @@
+//! Minting.
+//!
+//! The owner and the accounts it grants the minter role to, such as the land factory, can mint.
+//! `NonFungibleToken::mint` from `near-contract-standards` 3.1 only lets the owner mint and only
+//! charges the storage of the token itself, so the contract writes new tokens directly and its
+//! callers charge for everything a mint stores, royalties and series included.
+use crate::events::{MinterAdded, MinterRemoved};
+use crate::royalty::{assert_valid_royalty, Royalty};
+use crate::*;
+use near_sdk::assert_one_yocto;
+use near_sdk::json_types::U128;
+use std::collections::HashMap;
+
+#[near_bindgen]
+impl Contract {
+    /// Grants the minter role to `account_id`. Only the owner can call this.
+    #[payable]
+    pub fn add_minter(&mut self, account_id: ValidAccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        let account_id: AccountId = account_id.into();
+        assert!(
+            self.minters.insert(&account_id),
+            "The account is already a minter"
+        );
+        MinterAdded {
+            account_id: &account_id,
+        }
+        .emit();
+    }
+
+    /// Revokes the minter role from `account_id`. Only the owner can call this.
+    #[payable]
+    pub fn remove_minter(&mut self, account_id: ValidAccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        let account_id: AccountId = account_id.into();
+        assert!(
+            self.minters.remove(&account_id),
+            "The account is not a minter"
+        );
+        MinterRemoved {
+            account_id: &account_id,
+        }
+        .emit();
+    }
+
+    /// Returns whether `account_id` can mint. The owner always can.
+    pub fn is_minter(&self, account_id: ValidAccountId) -> bool {
+        account_id.as_ref() == &self.tokens.owner_id || self.minters.contains(account_id.as_ref())
+    }
+
+    pub fn get_minters(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<AccountId> {
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+        self.minters
+            .iter()
+            .skip(start as usize)
+            .take(limit.unwrap_or(self.minters.len()) as usize)
+            .collect()
+    }
+}
+
+impl Contract {
+    pub(crate) fn assert_minter(&self) {
+        let predecessor_id = env::predecessor_account_id();
+        assert!(
+            predecessor_id == self.tokens.owner_id || self.minters.contains(&predecessor_id),
+            "Only minters can call this method"
+        );
+    }
+
+    pub(crate) fn internal_mint(
+        &mut self,
+        token_id: TokenId,
+        owner_id: AccountId,
+        token_metadata: TokenMetadata,
+        royalty: Royalty,
+        transferable: bool,
+    ) -> Token {
+        assert_valid_royalty(&royalty);
+        assert!(
+            self.tokens.owner_by_id.get(&token_id).is_none(),
+            "token_id must be unique"
+        );
+
+        self.tokens.owner_by_id.insert(&token_id, &owner_id);
+        if let Some(token_metadata_by_id) = &mut self.tokens.token_metadata_by_id {
+            token_metadata_by_id.insert(&token_id, &token_metadata);
+        }
+        if let Some(tokens_per_owner) = &mut self.tokens.tokens_per_owner {
+            let mut token_ids = tokens_per_owner.get(&owner_id).unwrap_or_else(|| {
+                UnorderedSet::new(StorageKey::TokensPerOwner {
+                    account_hash: env::sha256(owner_id.as_bytes()),
+                })
+            });
+            token_ids.insert(&token_id);
+            tokens_per_owner.insert(&owner_id, &token_ids);
+        }
+        if !royalty.is_empty() {
+            self.royalties.insert(&token_id, &royalty);
+        }
+        if !transferable {
+            self.non_transferable.insert(&token_id);
+        }
+
+        Token {
+            token_id,
+            owner_id,
+            metadata: Some(token_metadata),
+            approved_account_ids: self.tokens.approvals_by_id.as_ref().map(|_| HashMap::new()),
+        }
+    }
+}
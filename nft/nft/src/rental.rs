@@ -0,0 +1,106 @@
+//! Rentals.
+//!
+//! The owner of a token, or an account it approved, can lease it to a user until a deadline, in the
+//! style of ERC-4907. The user role expires by itself with `env::block_timestamp()` and is cleared
+//! whenever the token changes hands, by `nft_transfer_call` only once the receiver kept it, so
+//! gameplay contracts only need `nft_user_of` to know who currently has usage rights.
+use crate::events::UserUpdated;
+use crate::*;
+use near_sdk::json_types::U64;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct TokenUser {
+    pub user_id: AccountId,
+    /// Unix timestamp in milliseconds from which the user loses its rights.
+    pub expires: u64,
+    /// Account that paid for the storage of the entry, and gets it back when it is replaced.
+    pub payer_id: AccountId,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Gives `user_id` the usage rights of the token until `expires`, a Unix timestamp in
+    /// milliseconds, replacing the current user if any. Only the owner of the token or an account
+    /// it approved can call this, and pays for the storage of the user. The storage of the user it
+    /// replaces goes back to whoever paid for it.
+    #[payable]
+    pub fn nft_set_user(&mut self, token_id: TokenId, user_id: ValidAccountId, expires: U64) {
+        assert!(
+            env::attached_deposit() >= 1,
+            "Requires attached deposit of at least 1 yoctoNEAR"
+        );
+        let owner_id = self
+            .tokens
+            .owner_by_id
+            .get(&token_id)
+            .unwrap_or_else(|| env::panic(b"Token not found"));
+        let predecessor_id = env::predecessor_account_id();
+        assert!(
+            predecessor_id == owner_id || self.is_approved(&token_id, &predecessor_id),
+            "Only the owner or an approved account can set the user"
+        );
+        assert!(expires.0 > now_ms(), "The rental must expire in the future");
+
+        // The storage of the replaced user goes back to whoever paid for it, the caller pays for
+        // the new one
+        let storage_usage_with_previous_user = env::storage_usage();
+        if let Some(previous_user) = self.users.remove(&token_id) {
+            let storage_released = storage_usage_with_previous_user - env::storage_usage();
+            Promise::new(previous_user.payer_id)
+                .transfer(Balance::from(storage_released) * env::storage_byte_cost());
+        }
+        let initial_storage_usage = env::storage_usage();
+        let user_id: AccountId = user_id.into();
+        self.users.insert(
+            &token_id,
+            &TokenUser {
+                user_id: user_id.clone(),
+                expires: expires.0,
+                payer_id: predecessor_id,
+            },
+        );
+        refund_deposit(env::storage_usage() - initial_storage_usage, 0);
+
+        UserUpdated {
+            token_id: &token_id,
+            user_id: Some(&user_id),
+            expires,
+        }
+        .emit();
+    }
+
+    /// Returns the account that currently has the usage rights of the token, if its rental
+    /// hasn't expired.
+    pub fn nft_user_of(&self, token_id: TokenId) -> Option<AccountId> {
+        self.internal_user(&token_id).map(|user| user.user_id)
+    }
+
+    /// Returns when the rental of the current user of the token expires, in milliseconds.
+    pub fn nft_user_expires(&self, token_id: TokenId) -> Option<U64> {
+        self.internal_user(&token_id).map(|user| U64(user.expires))
+    }
+}
+
+impl Contract {
+    fn internal_user(&self, token_id: &TokenId) -> Option<TokenUser> {
+        self.users
+            .get(token_id)
+            .filter(|user| user.expires > now_ms())
+    }
+
+    /// Revokes the user role of a token that changes hands.
+    pub(crate) fn internal_clear_user(&mut self, token_id: &TokenId) {
+        if self.users.remove(token_id).is_some() {
+            UserUpdated {
+                token_id,
+                user_id: None,
+                expires: U64(0),
+            }
+            .emit();
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    env::block_timestamp() / 1_000_000
+}
@@ -0,0 +1,108 @@
+//! Royalties and [NEP-199] payouts.
+//!
+//! Each token can carry royalties set at mint, mapping accounts to their share of every sale in
+//! basis points. `nft_payout` splits a sale price between those accounts and the token owner, who
+//! gets the rest, and marketplaces call `nft_transfer_payout` to transfer a sold token and learn
+//! how to split its price in one call.
+//!
+//! [NEP-199]: https://nomicon.io/Standards/Tokens/NonFungibleToken/Payout
+use crate::*;
+use near_sdk::assert_one_yocto;
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Shares are expressed in basis points, so the royalties of a token add up to at most 10 000.
+pub const ROYALTY_DENOMINATOR: u32 = 10_000;
+
+/// Account -> share of the sale price in basis points.
+pub type Royalty = HashMap<AccountId, u32>;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Payout {
+    pub payout: HashMap<AccountId, U128>,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns how a sale of the token for `balance` is split between its royalty accounts and its
+    /// owner. Panics if that takes more than `max_len_payout` accounts.
+    pub fn nft_payout(&self, token_id: TokenId, balance: U128, max_len_payout: u32) -> Payout {
+        let owner_id = self
+            .tokens
+            .owner_by_id
+            .get(&token_id)
+            .unwrap_or_else(|| env::panic(b"Token not found"));
+        self.internal_payout(&token_id, owner_id, balance.0, max_len_payout)
+    }
+
+    /// Transfers the token like `nft_transfer` and returns how `balance` is split between its
+    /// royalty accounts and its previous owner, like `nft_payout`.
+    #[payable]
+    pub fn nft_transfer_payout(
+        &mut self,
+        receiver_id: ValidAccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        balance: U128,
+        max_len_payout: u32,
+    ) -> Payout {
+        assert_one_yocto();
+        self.assert_transferable(&token_id);
+        self.internal_clear_user(&token_id);
+        let sender_id = env::predecessor_account_id();
+        let (previous_owner_id, approved_account_ids) = self.tokens.internal_transfer(
+            &sender_id,
+            receiver_id.as_ref(),
+            &token_id,
+            approval_id,
+            memo,
+        );
+        if let Some(approved_account_ids) = approved_account_ids {
+            refund_approved_account_ids(previous_owner_id.clone(), &approved_account_ids);
+        }
+        self.internal_payout(&token_id, previous_owner_id, balance.0, max_len_payout)
+    }
+}
+
+impl Contract {
+    fn internal_payout(
+        &self,
+        token_id: &TokenId,
+        owner_id: AccountId,
+        balance: Balance,
+        max_len_payout: u32,
+    ) -> Payout {
+        let mut payout = HashMap::new();
+        let mut paid = 0;
+        for (account_id, share) in self.royalties.get(token_id).unwrap_or_default() {
+            let amount = royalty_amount(balance, share);
+            paid += amount;
+            payout.insert(account_id, U128(amount));
+        }
+        payout.entry(owner_id).or_insert(U128(0)).0 += balance - paid;
+        assert!(
+            payout.len() <= max_len_payout as usize,
+            "The payout needs {} accounts, more than max_len_payout",
+            payout.len()
+        );
+        Payout { payout }
+    }
+}
+
+pub(crate) fn assert_valid_royalty(royalty: &Royalty) {
+    let total: u64 = royalty.values().map(|share| u64::from(*share)).sum();
+    assert!(
+        total <= u64::from(ROYALTY_DENOMINATOR),
+        "The royalties can't exceed 100%"
+    );
+}
+
+/// `share` basis points of `balance`, rounded down, without overflowing for any balance.
+fn royalty_amount(balance: Balance, share: u32) -> Balance {
+    let denominator = Balance::from(ROYALTY_DENOMINATOR);
+    let share = Balance::from(share);
+    balance / denominator * share + balance % denominator * share / denominator
+}
@@ -0,0 +1,221 @@
+//! Token series.
+//!
+//! A minter creates a series with the metadata, royalties and price its editions share, and the
+//! editions are minted as `series_id:edition` tokens, numbered from 1. The `copies` of the series
+//! metadata caps how many editions can ever be minted. The creator mints editions for free, and
+//! anyone can buy one for the price if the series has one, which goes to the creator.
+use crate::royalty::{assert_valid_royalty, Royalty};
+use crate::token::JsonToken;
+use crate::*;
+use near_sdk::assert_one_yocto;
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// Separates the series id from the edition number in token ids.
+pub const SERIES_DELIMITER: char = ':';
+
+pub type SeriesId = u64;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Series {
+    pub creator_id: AccountId,
+    /// Metadata every edition is minted with, `copies` being the maximum number of editions.
+    pub metadata: TokenMetadata,
+    /// Price of an edition in yoctoNEAR, `None` if only the creator can mint.
+    pub price: Option<Balance>,
+    pub royalty: Royalty,
+    /// Whether the editions can be transferred and approved.
+    pub transferable: bool,
+    /// Number of editions minted so far, burned ones included.
+    pub minted: u64,
+    /// Editions that currently exist.
+    pub tokens: UnorderedSet<TokenId>,
+    /// Accounts allowed to redeem the editions as tickets.
+    pub scanners: UnorderedSet<AccountId>,
+    /// Whether tickets can only be redeemed between their `starts_at` and `expires_at`.
+    pub redeem_within_validity: bool,
+    /// Whether tickets become non-transferable once redeemed.
+    pub non_transferable_once_redeemed: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonSeries {
+    pub series_id: SeriesId,
+    pub creator_id: AccountId,
+    pub metadata: TokenMetadata,
+    pub price: Option<U128>,
+    pub royalty: Royalty,
+    pub transferable: bool,
+    pub minted: u64,
+    pub redeem_within_validity: bool,
+    pub non_transferable_once_redeemed: bool,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Creates a series owned by the caller and returns its id. Its editions can't be transferred
+    /// nor approved if `transferable` is `false`. Only minters can call this.
+    #[payable]
+    pub fn nft_create_series(
+        &mut self,
+        metadata: TokenMetadata,
+        price: Option<U128>,
+        royalty: Option<Royalty>,
+        transferable: Option<bool>,
+    ) -> SeriesId {
+        self.assert_minter();
+        let royalty = royalty.unwrap_or_default();
+        assert_valid_royalty(&royalty);
+        assert!(
+            metadata.copies != Some(0),
+            "The series should allow at least one copy"
+        );
+        let initial_storage_usage = env::storage_usage();
+        let series_id = self.series.len() + 1;
+        self.series.insert(
+            &series_id,
+            &Series {
+                creator_id: env::predecessor_account_id(),
+                metadata,
+                price: price.map(|price| price.0),
+                royalty,
+                transferable: transferable.unwrap_or(true),
+                minted: 0,
+                tokens: UnorderedSet::new(StorageKey::SeriesTokens { series_id }),
+                scanners: UnorderedSet::new(StorageKey::SeriesScanners { series_id }),
+                redeem_within_validity: false,
+                non_transferable_once_redeemed: false,
+            },
+        );
+        refund_deposit(env::storage_usage() - initial_storage_usage, 0);
+        series_id
+    }
+
+    /// Sets the price of an edition, or stops the sale with `None`. Only the creator of the series
+    /// can call this.
+    #[payable]
+    pub fn nft_set_series_price(&mut self, series_id: SeriesId, price: Option<U128>) {
+        assert_one_yocto();
+        let mut series = self.internal_get_series_as_creator(series_id);
+        series.price = price.map(|price| price.0);
+        self.series.insert(&series_id, &series);
+    }
+
+    /// Mints the next edition of the series to `receiver_id`. The creator of the series only pays
+    /// for storage, anyone else also pays the price of the series.
+    #[payable]
+    pub fn nft_mint_series(&mut self, series_id: SeriesId, receiver_id: ValidAccountId) -> Token {
+        let initial_storage_usage = env::storage_usage();
+        let mut series = self.internal_get_series(series_id);
+        let price = if env::predecessor_account_id() == series.creator_id {
+            0
+        } else {
+            series
+                .price
+                .unwrap_or_else(|| env::panic(b"The series isn't for sale"))
+        };
+        if let Some(copies) = series.metadata.copies {
+            assert!(
+                series.minted < copies,
+                "All the copies of the series are minted"
+            );
+        }
+
+        series.minted += 1;
+        let token_id = format!("{}{}{}", series_id, SERIES_DELIMITER, series.minted);
+        let mut metadata = series.metadata.clone();
+        metadata.title = metadata
+            .title
+            .map(|title| format!("{} #{}", title, series.minted));
+        metadata.issued_at = Some((env::block_timestamp() / 1_000_000).to_string());
+        let token = self.internal_mint(
+            token_id.clone(),
+            receiver_id.into(),
+            metadata,
+            series.royalty.clone(),
+            series.transferable,
+        );
+        series.tokens.insert(&token_id);
+        self.series.insert(&series_id, &series);
+
+        refund_deposit(env::storage_usage() - initial_storage_usage, price);
+        if price > 0 {
+            Promise::new(series.creator_id).transfer(price);
+        }
+        token
+    }
+
+    pub fn nft_get_series(&self, series_id: SeriesId) -> Option<JsonSeries> {
+        self.series.get(&series_id).map(|series| JsonSeries {
+            series_id,
+            creator_id: series.creator_id,
+            metadata: series.metadata,
+            price: series.price.map(U128),
+            royalty: series.royalty,
+            transferable: series.transferable,
+            minted: series.minted,
+            redeem_within_validity: series.redeem_within_validity,
+            non_transferable_once_redeemed: series.non_transferable_once_redeemed,
+        })
+    }
+
+    /// Returns the editions of the series that currently exist.
+    pub fn nft_tokens_by_series(
+        &self,
+        series_id: SeriesId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<JsonToken> {
+        let token_ids = self.internal_get_series(series_id).tokens;
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+        token_ids
+            .iter()
+            .skip(start as usize)
+            .take(limit.unwrap_or(token_ids.len()) as usize)
+            .filter_map(|token_id| self.internal_token(&token_id))
+            .collect()
+    }
+
+    /// Returns the number of editions of the series that currently exist.
+    pub fn nft_supply_for_series(&self, series_id: SeriesId) -> U128 {
+        U128(u128::from(self.internal_get_series(series_id).tokens.len()))
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_get_series(&self, series_id: SeriesId) -> Series {
+        self.series
+            .get(&series_id)
+            .unwrap_or_else(|| env::panic(b"The series doesn't exist"))
+    }
+
+    pub(crate) fn internal_get_series_as_creator(&self, series_id: SeriesId) -> Series {
+        let series = self.internal_get_series(series_id);
+        assert_eq!(
+            env::predecessor_account_id(),
+            series.creator_id,
+            "Only the creator of the series can call this method"
+        );
+        series
+    }
+
+    /// Forgets a burned edition, if `token_id` is one.
+    pub(crate) fn internal_remove_series_token(&mut self, token_id: &TokenId) {
+        let series_id = match series_id_of(token_id) {
+            Some(series_id) => series_id,
+            None => return,
+        };
+        if let Some(mut series) = self.series.get(&series_id) {
+            series.tokens.remove(token_id);
+            self.series.insert(&series_id, &series);
+        }
+    }
+}
+
+/// The series the token is an edition of, if it's one.
+pub(crate) fn series_id_of(token_id: &str) -> Option<SeriesId> {
+    token_id
+        .split_once(SERIES_DELIMITER)
+        .and_then(|(series_id, _)| series_id.parse().ok())
+}
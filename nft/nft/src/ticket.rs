@@ -0,0 +1,115 @@
+//! Event tickets.
+//!
+//! The editions of a series are the tickets of an event. Its creator designates the scanner
+//! accounts that check attendees in, and a scanner redeems each ticket once with `redeem_ticket`.
+//! The creator can restrict redemption to the `starts_at`..`expires_at` window of the ticket
+//! metadata, and make tickets non-transferable once they're redeemed.
+use crate::events::TicketRedeemed;
+use crate::series::series_id_of;
+use crate::validity::Validity;
+use crate::*;
+use near_sdk::assert_one_yocto;
+use near_sdk::json_types::U128;
+
+#[near_bindgen]
+impl Contract {
+    /// Lets `account_id` redeem the tickets of the series. Only the creator of the series can call
+    /// this, and pays for the storage of the scanner.
+    #[payable]
+    pub fn add_scanner(&mut self, series_id: SeriesId, account_id: ValidAccountId) {
+        let initial_storage_usage = env::storage_usage();
+        let mut series = self.internal_get_series_as_creator(series_id);
+        assert!(
+            series.scanners.insert(account_id.as_ref()),
+            "The account is already a scanner"
+        );
+        self.series.insert(&series_id, &series);
+        refund_deposit(env::storage_usage() - initial_storage_usage, 0);
+    }
+
+    /// Only the creator of the series can call this.
+    #[payable]
+    pub fn remove_scanner(&mut self, series_id: SeriesId, account_id: ValidAccountId) {
+        assert_one_yocto();
+        let mut series = self.internal_get_series_as_creator(series_id);
+        assert!(
+            series.scanners.remove(account_id.as_ref()),
+            "The account is not a scanner"
+        );
+        self.series.insert(&series_id, &series);
+    }
+
+    pub fn get_scanners(
+        &self,
+        series_id: SeriesId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<AccountId> {
+        let scanners = self.internal_get_series(series_id).scanners;
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+        scanners
+            .iter()
+            .skip(start as usize)
+            .take(limit.unwrap_or(scanners.len()) as usize)
+            .collect()
+    }
+
+    /// Sets whether the tickets of the series can only be redeemed between their `starts_at` and
+    /// `expires_at`, and whether they become non-transferable once redeemed. Only the creator of
+    /// the series can call this.
+    #[payable]
+    pub fn set_ticket_rules(
+        &mut self,
+        series_id: SeriesId,
+        redeem_within_validity: bool,
+        non_transferable_once_redeemed: bool,
+    ) {
+        assert_one_yocto();
+        let mut series = self.internal_get_series_as_creator(series_id);
+        series.redeem_within_validity = redeem_within_validity;
+        series.non_transferable_once_redeemed = non_transferable_once_redeemed;
+        self.series.insert(&series_id, &series);
+    }
+
+    /// Marks the ticket as redeemed. Only the scanners of its series can call this, once per
+    /// ticket.
+    #[payable]
+    pub fn redeem_ticket(&mut self, token_id: TokenId) {
+        let initial_storage_usage = env::storage_usage();
+        let series = series_id_of(&token_id)
+            .and_then(|series_id| self.series.get(&series_id))
+            .unwrap_or_else(|| env::panic(b"The token is not a ticket"));
+        let owner_id = self
+            .tokens
+            .owner_by_id
+            .get(&token_id)
+            .unwrap_or_else(|| env::panic(b"Token not found"));
+        let scanner_id = env::predecessor_account_id();
+        assert!(
+            series.scanners.contains(&scanner_id),
+            "Only scanners of the event can redeem its tickets"
+        );
+        if series.redeem_within_validity {
+            match self.internal_validity(&token_id) {
+                Validity::NotStarted => env::panic(b"The ticket isn't valid yet"),
+                Validity::Expired => env::panic(b"The ticket has expired"),
+                Validity::Valid => {}
+            }
+        }
+        assert!(
+            self.redeemed.insert(&token_id),
+            "The ticket is already redeemed"
+        );
+        if series.non_transferable_once_redeemed {
+            self.non_transferable.insert(&token_id);
+        }
+        refund_deposit(env::storage_usage() - initial_storage_usage, 0);
+
+        TicketRedeemed {
+            token_id: &token_id,
+            owner_id: &owner_id,
+            scanner_id: &scanner_id,
+        }
+        .emit();
+    }
+}
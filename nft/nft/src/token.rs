@@ -0,0 +1,162 @@
+//! Core and approval methods of [NEP-171] and [NEP-178].
+//!
+//! The contract implements them itself rather than with the `near-contract-standards` macros, so
+//! that it can block transfers and approvals of non-transferable tokens, such as attendance
+//! badges, and of tokens outside their validity window, and return the extra fields of a token
+//! from `nft_token`. Everything else is delegated to `NonFungibleToken`.
+//!
+//! [NEP-171]: https://nomicon.io/Standards/Tokens/NonFungibleToken/Core
+//! [NEP-178]: https://nomicon.io/Standards/Tokens/NonFungibleToken/ApprovalManagement
+use crate::*;
+use near_contract_standards::non_fungible_token::approval::NonFungibleTokenApproval;
+use near_contract_standards::non_fungible_token::core::{
+    NonFungibleTokenCore, NonFungibleTokenResolver,
+};
+use near_sdk::serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A token as returned by `nft_token`: the fields of `Token` followed by the contract's own.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonToken {
+    pub token_id: TokenId,
+    pub owner_id: AccountId,
+    pub metadata: Option<TokenMetadata>,
+    pub approved_account_ids: Option<HashMap<AccountId, u64>>,
+    /// Whether the token can be transferred and approved. It can still be burned either way.
+    pub transferable: bool,
+    /// Whether the token was redeemed as an event ticket.
+    pub redeemed: bool,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[payable]
+    pub fn nft_transfer(
+        &mut self,
+        receiver_id: ValidAccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+    ) {
+        self.assert_transferable(&token_id);
+        self.internal_clear_user(&token_id);
+        self.tokens
+            .nft_transfer(receiver_id, token_id, approval_id, memo)
+    }
+
+    #[payable]
+    pub fn nft_transfer_call(
+        &mut self,
+        receiver_id: ValidAccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<bool> {
+        self.assert_transferable(&token_id);
+        // The receiver can still return the token, so the user is only cleared by
+        // `nft_resolve_transfer` once the transfer is final
+        self.tokens
+            .nft_transfer_call(receiver_id, token_id, approval_id, memo, msg)
+    }
+
+    pub fn nft_token(&self, token_id: TokenId) -> Option<JsonToken> {
+        self.internal_token(&token_id)
+    }
+
+    #[private]
+    pub fn nft_resolve_transfer(
+        &mut self,
+        previous_owner_id: AccountId,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approved_account_ids: Option<HashMap<AccountId, u64>>,
+    ) -> bool {
+        let transferred = self.tokens.nft_resolve_transfer(
+            previous_owner_id,
+            receiver_id,
+            token_id.clone(),
+            approved_account_ids,
+        );
+        if transferred {
+            self.internal_clear_user(&token_id);
+        }
+        transferred
+    }
+
+    #[payable]
+    pub fn nft_approve(
+        &mut self,
+        token_id: TokenId,
+        account_id: ValidAccountId,
+        msg: Option<String>,
+    ) -> Option<Promise> {
+        self.assert_transferable(&token_id);
+        self.tokens.nft_approve(token_id, account_id, msg)
+    }
+
+    #[payable]
+    pub fn nft_revoke(&mut self, token_id: TokenId, account_id: ValidAccountId) {
+        self.tokens.nft_revoke(token_id, account_id)
+    }
+
+    #[payable]
+    pub fn nft_revoke_all(&mut self, token_id: TokenId) {
+        self.tokens.nft_revoke_all(token_id)
+    }
+
+    pub fn nft_is_approved(
+        &self,
+        token_id: TokenId,
+        approved_account_id: ValidAccountId,
+        approval_id: Option<u64>,
+    ) -> bool {
+        self.tokens
+            .nft_is_approved(token_id, approved_account_id, approval_id)
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_token(&self, token_id: &TokenId) -> Option<JsonToken> {
+        let owner_id = self.tokens.owner_by_id.get(token_id)?;
+        let metadata = self
+            .tokens
+            .token_metadata_by_id
+            .as_ref()
+            .and_then(|token_metadata_by_id| token_metadata_by_id.get(token_id));
+        let approved_account_ids = self
+            .tokens
+            .approvals_by_id
+            .as_ref()
+            .map(|approvals_by_id| approvals_by_id.get(token_id).unwrap_or_default());
+        Some(JsonToken {
+            token_id: token_id.clone(),
+            owner_id,
+            metadata,
+            approved_account_ids,
+            transferable: !self.non_transferable.contains(token_id),
+            redeemed: self.redeemed.contains(token_id),
+        })
+    }
+
+    /// Whether the owner of the token approved `account_id`.
+    pub(crate) fn is_approved(&self, token_id: &TokenId, account_id: &AccountId) -> bool {
+        self.tokens
+            .approvals_by_id
+            .as_ref()
+            .and_then(|approvals_by_id| approvals_by_id.get(token_id))
+            .map_or(false, |approved_account_ids| {
+                approved_account_ids.contains_key(account_id)
+            })
+    }
+
+    /// Panics if the token can't be transferred nor approved right now.
+    pub(crate) fn assert_transferable(&self, token_id: &TokenId) {
+        assert!(
+            !self.non_transferable.contains(token_id),
+            "The token is non-transferable"
+        );
+        self.assert_valid_now(token_id);
+    }
+}
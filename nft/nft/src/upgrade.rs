@@ -0,0 +1,363 @@
+//! Code upgrades and state migrations.
+//!
+//! The owner calls `upgrade` with the new wasm as the raw input of the call. The contract deploys
+//! it on its own account and calls `migrate`, which reads the state in whatever layout it was
+//! stored and rewrites it in the current one. Each `ContractVn` is a frozen copy of a deployed
+//! layout and converts into the next one, so an old state goes through every migration in order.
+use crate::*;
+use near_sdk::{assert_one_yocto, Gas};
+
+/// Gas kept by `upgrade` for itself, the rest of the prepaid gas goes to `migrate`.
+const GAS_FOR_UPGRADE: Gas = 10_000_000_000_000;
+
+/// Least gas for `migrate`, which rewrites every series when the state predates `ContractV6`.
+/// `upgrade` checks it before deploying the code.
+const MIN_GAS_FOR_MIGRATE: Gas = 20_000_000_000_000;
+
+const STATE_KEY: &[u8] = b"STATE";
+
+/// Layout of the first deployments, which only stored the tokens and the contract metadata.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ContractV1 {
+    pub tokens: NonFungibleToken,
+    pub metadata: LazyOption<NFTContractMetadata>,
+}
+
+/// Adds the royalties.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ContractV2 {
+    pub tokens: NonFungibleToken,
+    pub metadata: LazyOption<NFTContractMetadata>,
+    pub royalties: LookupMap<TokenId, Royalty>,
+}
+
+impl From<ContractV1> for ContractV2 {
+    fn from(ContractV1 { tokens, metadata }: ContractV1) -> Self {
+        Self {
+            tokens,
+            metadata,
+            royalties: LookupMap::new(StorageKey::Royalties),
+        }
+    }
+}
+
+/// Adds the minters.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ContractV3 {
+    pub tokens: NonFungibleToken,
+    pub metadata: LazyOption<NFTContractMetadata>,
+    pub royalties: LookupMap<TokenId, Royalty>,
+    pub minters: UnorderedSet<AccountId>,
+}
+
+impl From<ContractV2> for ContractV3 {
+    fn from(contract: ContractV2) -> Self {
+        Self {
+            tokens: contract.tokens,
+            metadata: contract.metadata,
+            royalties: contract.royalties,
+            minters: UnorderedSet::new(StorageKey::Minters),
+        }
+    }
+}
+
+/// Layout of the series when they were added.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct SeriesV1 {
+    pub creator_id: AccountId,
+    pub metadata: TokenMetadata,
+    pub price: Option<Balance>,
+    pub royalty: Royalty,
+    pub minted: u64,
+    pub tokens: UnorderedSet<TokenId>,
+}
+
+impl SeriesV1 {
+    fn into_v2(self) -> SeriesV2 {
+        SeriesV2 {
+            creator_id: self.creator_id,
+            metadata: self.metadata,
+            price: self.price,
+            royalty: self.royalty,
+            transferable: true,
+            minted: self.minted,
+            tokens: self.tokens,
+        }
+    }
+}
+
+/// Adds the series.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ContractV4 {
+    pub tokens: NonFungibleToken,
+    pub metadata: LazyOption<NFTContractMetadata>,
+    pub royalties: LookupMap<TokenId, Royalty>,
+    pub minters: UnorderedSet<AccountId>,
+    pub series: UnorderedMap<SeriesId, SeriesV1>,
+}
+
+impl From<ContractV3> for ContractV4 {
+    fn from(contract: ContractV3) -> Self {
+        Self {
+            tokens: contract.tokens,
+            metadata: contract.metadata,
+            royalties: contract.royalties,
+            minters: contract.minters,
+            series: UnorderedMap::new(StorageKey::Series),
+        }
+    }
+}
+
+/// Layout of the series once they could be non-transferable.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct SeriesV2 {
+    pub creator_id: AccountId,
+    pub metadata: TokenMetadata,
+    pub price: Option<Balance>,
+    pub royalty: Royalty,
+    pub transferable: bool,
+    pub minted: u64,
+    pub tokens: UnorderedSet<TokenId>,
+}
+
+impl SeriesV2 {
+    fn into_current(self, series_id: SeriesId) -> Series {
+        Series {
+            creator_id: self.creator_id,
+            metadata: self.metadata,
+            price: self.price,
+            royalty: self.royalty,
+            transferable: self.transferable,
+            minted: self.minted,
+            tokens: self.tokens,
+            scanners: UnorderedSet::new(StorageKey::SeriesScanners { series_id }),
+            redeem_within_validity: false,
+            non_transferable_once_redeemed: false,
+        }
+    }
+}
+
+/// Adds the non-transferable tokens and series.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ContractV5 {
+    pub tokens: NonFungibleToken,
+    pub metadata: LazyOption<NFTContractMetadata>,
+    pub royalties: LookupMap<TokenId, Royalty>,
+    pub minters: UnorderedSet<AccountId>,
+    pub series: UnorderedMap<SeriesId, SeriesV2>,
+    pub non_transferable: LookupSet<TokenId>,
+}
+
+impl From<ContractV4> for ContractV5 {
+    fn from(contract: ContractV4) -> Self {
+        Self {
+            tokens: contract.tokens,
+            metadata: contract.metadata,
+            royalties: contract.royalties,
+            minters: contract.minters,
+            series: migrate_series(contract.series, |_, series| series.into_v2()),
+            non_transferable: LookupSet::new(StorageKey::NonTransferable),
+        }
+    }
+}
+
+/// Adds the redeemed tickets, and the scanners and ticket rules of the series.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ContractV6 {
+    pub tokens: NonFungibleToken,
+    pub metadata: LazyOption<NFTContractMetadata>,
+    pub royalties: LookupMap<TokenId, Royalty>,
+    pub minters: UnorderedSet<AccountId>,
+    pub series: UnorderedMap<SeriesId, Series>,
+    pub non_transferable: LookupSet<TokenId>,
+    pub redeemed: LookupSet<TokenId>,
+}
+
+impl From<ContractV5> for ContractV6 {
+    fn from(contract: ContractV5) -> Self {
+        Self {
+            tokens: contract.tokens,
+            metadata: contract.metadata,
+            royalties: contract.royalties,
+            minters: contract.minters,
+            series: migrate_series(contract.series, |series_id, series| {
+                series.into_current(series_id)
+            }),
+            non_transferable: contract.non_transferable,
+            redeemed: LookupSet::new(StorageKey::Redeemed),
+        }
+    }
+}
+
+/// Adds the switch that enforces the validity window of tickets.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ContractV7 {
+    pub tokens: NonFungibleToken,
+    pub metadata: LazyOption<NFTContractMetadata>,
+    pub royalties: LookupMap<TokenId, Royalty>,
+    pub minters: UnorderedSet<AccountId>,
+    pub series: UnorderedMap<SeriesId, Series>,
+    pub non_transferable: LookupSet<TokenId>,
+    pub redeemed: LookupSet<TokenId>,
+    pub validity_enforced: bool,
+}
+
+impl From<ContractV6> for ContractV7 {
+    fn from(contract: ContractV6) -> Self {
+        Self {
+            tokens: contract.tokens,
+            metadata: contract.metadata,
+            royalties: contract.royalties,
+            minters: contract.minters,
+            series: contract.series,
+            non_transferable: contract.non_transferable,
+            redeemed: contract.redeemed,
+            validity_enforced: false,
+        }
+    }
+}
+
+/// Rewrites every value of `series` with `convert`. The map keeps its prefixes, so its keys and
+/// the collections inside the series stay where they are.
+fn migrate_series<S, T>(
+    series: UnorderedMap<SeriesId, S>,
+    convert: impl Fn(SeriesId, S) -> T,
+) -> UnorderedMap<SeriesId, T>
+where
+    S: BorshDeserialize + BorshSerialize,
+    T: BorshDeserialize + BorshSerialize,
+{
+    let entries = series.to_vec();
+    let mut migrated: UnorderedMap<SeriesId, T> =
+        UnorderedMap::try_from_slice(&series.try_to_vec().unwrap()).unwrap();
+    for (series_id, old) in entries {
+        migrated.insert(&series_id, &convert(series_id, old));
+    }
+    migrated
+}
+
+pub enum VersionedContract {
+    V1(ContractV1),
+    V2(ContractV2),
+    V3(ContractV3),
+    V4(ContractV4),
+    V5(ContractV5),
+    V6(ContractV6),
+    V7(ContractV7),
+    Current(Contract),
+}
+
+impl VersionedContract {
+    /// Reads the stored state, trying the layouts from the newest to the oldest. Borsh requires
+    /// the whole state to be consumed, so a layout with different fields doesn't match.
+    fn read() -> Self {
+        let state = env::storage_read(STATE_KEY)
+            .unwrap_or_else(|| env::panic(b"The contract is not initialized"));
+        if let Ok(contract) = Contract::try_from_slice(&state) {
+            return Self::Current(contract);
+        }
+        if let Ok(contract) = ContractV7::try_from_slice(&state) {
+            return Self::V7(contract);
+        }
+        if let Ok(contract) = ContractV6::try_from_slice(&state) {
+            return Self::V6(contract);
+        }
+        if let Ok(contract) = ContractV5::try_from_slice(&state) {
+            return Self::V5(contract);
+        }
+        if let Ok(contract) = ContractV4::try_from_slice(&state) {
+            return Self::V4(contract);
+        }
+        if let Ok(contract) = ContractV3::try_from_slice(&state) {
+            return Self::V3(contract);
+        }
+        if let Ok(contract) = ContractV2::try_from_slice(&state) {
+            return Self::V2(contract);
+        }
+        if let Ok(contract) = ContractV1::try_from_slice(&state) {
+            return Self::V1(contract);
+        }
+        env::panic(b"The state has an unknown layout")
+    }
+
+    /// Account allowed to migrate the state next to the contract account itself.
+    fn owner_id(&self) -> AccountId {
+        match self {
+            Self::V1(contract) => contract.tokens.owner_id.clone(),
+            Self::V2(contract) => contract.tokens.owner_id.clone(),
+            Self::V3(contract) => contract.tokens.owner_id.clone(),
+            Self::V4(contract) => contract.tokens.owner_id.clone(),
+            Self::V5(contract) => contract.tokens.owner_id.clone(),
+            Self::V6(contract) => contract.tokens.owner_id.clone(),
+            Self::V7(contract) => contract.tokens.owner_id.clone(),
+            Self::Current(contract) => contract.tokens.owner_id.clone(),
+        }
+    }
+}
+
+impl From<VersionedContract> for Contract {
+    fn from(state: VersionedContract) -> Self {
+        match state {
+            VersionedContract::V1(contract) => VersionedContract::V2(contract.into()).into(),
+            VersionedContract::V2(contract) => VersionedContract::V3(contract.into()).into(),
+            VersionedContract::V3(contract) => VersionedContract::V4(contract.into()).into(),
+            VersionedContract::V4(contract) => VersionedContract::V5(contract.into()).into(),
+            VersionedContract::V5(contract) => VersionedContract::V6(contract.into()).into(),
+            VersionedContract::V6(contract) => VersionedContract::V7(contract.into()).into(),
+            VersionedContract::V7(ContractV7 {
+                tokens,
+                metadata,
+                royalties,
+                minters,
+                series,
+                non_transferable,
+                redeemed,
+                validity_enforced,
+            }) => Contract {
+                tokens,
+                metadata,
+                royalties,
+                minters,
+                series,
+                non_transferable,
+                redeemed,
+                validity_enforced,
+                users: LookupMap::new(StorageKey::Users),
+            },
+            VersionedContract::Current(contract) => contract,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Rewrites the stored state in the current layout. Called by `upgrade` after deploying new
+    /// code, or by the owner directly.
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let state = VersionedContract::read();
+        let predecessor_id = env::predecessor_account_id();
+        assert!(
+            predecessor_id == env::current_account_id() || predecessor_id == state.owner_id(),
+            "Only the owner can call this method"
+        );
+        state.into()
+    }
+
+    /// Deploys the wasm passed as the raw input of the call and migrates the state to it. Only
+    /// the owner can call this.
+    #[payable]
+    pub fn upgrade(&mut self) -> Promise {
+        assert_one_yocto();
+        self.assert_owner();
+        let code = env::input().unwrap_or_else(|| env::panic(b"The code is missing"));
+        let migrate_gas = env::prepaid_gas().saturating_sub(env::used_gas() + GAS_FOR_UPGRADE);
+        assert!(
+            migrate_gas >= MIN_GAS_FOR_MIGRATE,
+            "Not enough gas attached to migrate the state"
+        );
+        Promise::new(env::current_account_id())
+            .deploy_contract(code)
+            .function_call(b"migrate".to_vec(), Vec::new(), 0, migrate_gas)
+    }
+}
@@ -0,0 +1,94 @@
+//! Validity windows.
+//!
+//! `TokenMetadata` can limit when a token is valid with `starts_at` and `expires_at`, Unix
+//! timestamps in milliseconds, for items such as season passes and temporary land permits. The
+//! contract ignores them unless the owner enables enforcement: then tokens outside their window
+//! can't be transferred nor approved, and anyone can burn an expired token with
+//! `nft_burn_expired`, which refunds its storage to its owner.
+use crate::events::NftBurn;
+use crate::*;
+use near_sdk::assert_one_yocto;
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Validity {
+    NotStarted,
+    Valid,
+    Expired,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Enables or disables the enforcement of validity windows. Only the owner can call this.
+    #[payable]
+    pub fn set_validity_enforced(&mut self, enforced: bool) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.validity_enforced = enforced;
+    }
+
+    pub fn is_validity_enforced(&self) -> bool {
+        self.validity_enforced
+    }
+
+    /// Burns a token past its `expires_at` and refunds its storage to its owner. Anyone can call
+    /// this while validity windows are enforced.
+    pub fn nft_burn_expired(&mut self, token_id: TokenId) {
+        assert!(self.validity_enforced, "Validity windows are not enforced");
+        let owner_id = self
+            .tokens
+            .owner_by_id
+            .get(&token_id)
+            .unwrap_or_else(|| env::panic(b"Token not found"));
+        assert_eq!(
+            self.internal_validity(&token_id),
+            Validity::Expired,
+            "The token hasn't expired"
+        );
+
+        self.internal_burn(&token_id, &owner_id);
+        NftBurn {
+            owner_id: &owner_id,
+            token_ids: &[&token_id],
+            authorized_id: None,
+            memo: Some("expired"),
+        }
+        .emit();
+    }
+}
+
+impl Contract {
+    /// Where the current block stands in the validity window of the token. Timestamps that aren't
+    /// milliseconds are ignored.
+    pub(crate) fn internal_validity(&self, token_id: &TokenId) -> Validity {
+        let metadata = match self
+            .tokens
+            .token_metadata_by_id
+            .as_ref()
+            .and_then(|token_metadata_by_id| token_metadata_by_id.get(token_id))
+        {
+            Some(metadata) => metadata,
+            None => return Validity::Valid,
+        };
+        let now = env::block_timestamp() / 1_000_000;
+        let timestamp = |timestamp: Option<String>| timestamp.and_then(|t| t.parse::<u64>().ok());
+        if timestamp(metadata.starts_at).map_or(false, |starts_at| now < starts_at) {
+            Validity::NotStarted
+        } else if timestamp(metadata.expires_at).map_or(false, |expires_at| now >= expires_at) {
+            Validity::Expired
+        } else {
+            Validity::Valid
+        }
+    }
+
+    /// Panics if validity windows are enforced and the token is outside its window.
+    pub(crate) fn assert_valid_now(&self, token_id: &TokenId) {
+        if !self.validity_enforced {
+            return;
+        }
+        match self.internal_validity(token_id) {
+            Validity::NotStarted => env::panic(b"The token isn't valid yet"),
+            Validity::Expired => env::panic(b"The token has expired"),
+            Validity::Valid => {}
+        }
+    }
+}
@@ -7,6 +7,7 @@ use near_sdk::PromiseResult;
 use serde_json::json;
 
 use crate::utils::{ext_fungible_token, GAS_FOR_FT_TRANSFER, GAS_FOR_RESOLVE_TRANSFER};
+mod upgrade;
 mod utils;
 
 #[near_bindgen]
@@ -293,5 +294,50 @@ mod tests {
         let contract = setup_contract();
         assert_eq!(contract.amount_claimed().0, 0);
     }
+
+    // Layout of the deployed contracts, frozen so that changing `Contract` without a migration
+    // fails `migrate_from_v0`
+    #[derive(BorshSerialize)]
+    struct ContractV0 {
+        owner: AccountId,
+        token: AccountId,
+        amount: u128,
+        amount_claimed: u128,
+        start: u64,
+        duration: u64,
+        is_active: bool,
+    }
+
+    #[test]
+    fn migrate_from_v0() {
+        let mut context = get_context(vec![], false);
+        context.predecessor_account_id = "alice.testnet".to_string();
+        testing_env!(context);
+        env::state_write(&ContractV0 {
+            owner: "alice.testnet".to_string(),
+            token: "token.testnet".to_string(),
+            amount: 1000000000000000000000000,
+            amount_claimed: 250000000000000000000000,
+            start: 1629055854000000000,
+            duration: 31556952000000000,
+            is_active: false,
+        });
+        let contract = Contract::migrate();
+        assert_eq!(contract.owner(), "alice.testnet");
+        assert_eq!(contract.token(), "token.testnet");
+        assert_eq!(contract.amount().0, 1000000000000000000000000);
+        assert_eq!(contract.amount_claimed().0, 250000000000000000000000);
+        assert_eq!(contract.start, 1629055854000000000);
+        assert_eq!(contract.duration, 31556952000000000);
+        assert!(!contract.is_active);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can migrate the contract")]
+    fn migrate_not_owner() {
+        let contract = setup_contract();
+        env::state_write(&contract);
+        Contract::migrate();
+    }
     // ... (más tests aquí)
 }
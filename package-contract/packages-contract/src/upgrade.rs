@@ -0,0 +1,81 @@
+// Upgrades of the package contract. The owner set in `new` deploys the new code with `upgrade`,
+// and `migrate` carries the package amount and what was claimed over to it.
+use crate::*;
+use near_sdk::{require, Gas};
+
+// Gas kept by `upgrade` for itself, the rest of the prepaid gas is attached to `migrate`
+const GAS_FOR_UPGRADE: Gas = Gas(10_000_000_000_000);
+
+// Least gas attached to `migrate`, `upgrade` fails before deploying the code below that
+const MIN_GAS_FOR_MIGRATE: Gas = Gas(20_000_000_000_000);
+
+const STATE_KEY: &[u8] = b"STATE";
+
+pub enum VersionedContract {
+    Current(Contract),
+}
+
+impl VersionedContract {
+    fn read() -> Self {
+        let state = env::storage_read(STATE_KEY)
+            .unwrap_or_else(|| env::panic_str("ERR_CONTRACT_NOT_INITIALIZED"));
+        if let Ok(contract) = Contract::try_from_slice(&state) {
+            return Self::Current(contract);
+        }
+        env::panic_str("ERR_UNKNOWN_STATE_LAYOUT")
+    }
+
+    fn owner(&self) -> AccountId {
+        match self {
+            Self::Current(contract) => contract.owner(),
+        }
+    }
+}
+
+impl From<VersionedContract> for Contract {
+    fn from(state: VersionedContract) -> Self {
+        match state {
+            VersionedContract::Current(contract) => contract,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // Rewrites the stored state in the current layout. Called by `upgrade` once the new code is
+    // deployed, or by the owner directly.
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let state = VersionedContract::read();
+        let predecessor_id = env::predecessor_account_id();
+        assert!(
+            predecessor_id == env::current_account_id() || predecessor_id == state.owner(),
+            "Only the owner can migrate the contract"
+        );
+        state.into()
+    }
+
+    // Deploys the wasm passed as the raw input of the call and migrates the state to it
+    #[payable]
+    pub fn upgrade(&mut self) -> Promise {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.owner,
+            "Only the owner can upgrade the contract"
+        );
+        near_sdk::assert_one_yocto();
+
+        let code = env::input().unwrap_or_else(|| env::panic_str("ERR_MISSING_CODE"));
+        let migrate_gas =
+            env::prepaid_gas().0.saturating_sub(env::used_gas().0 + GAS_FOR_UPGRADE.0);
+        require!(migrate_gas >= MIN_GAS_FOR_MIGRATE.0, "ERR_NOT_ENOUGH_GAS");
+        Promise::new(env::current_account_id())
+            .deploy_contract(code)
+            .function_call(
+                "migrate".to_string(),
+                Vec::new(),
+                0,
+                Gas(migrate_gas),
+            )
+    }
+}
@@ -7,6 +7,7 @@ use serde_json::json;
 use near_sdk::collections::Vector;
 
 use crate::utils::{ext_fungible_token, GAS_FOR_FT_TRANSFER, ONE_MONTH, XCC_GAS};
+mod upgrade;
 mod utils;
 
 near_sdk::setup_alloc!();
@@ -527,4 +528,64 @@ mod tests {
 
         assert_eq!(contract.recipient(), "changed.near");
     }
+
+    // Layout of the deployed vesting contracts, frozen so that changing `Contract` without a
+    // migration fails `test_migrate_from_v0`
+    #[derive(BorshSerialize)]
+    struct ContractV0 {
+        owner: AccountId,
+        recipient: AccountId,
+        token: AccountId,
+        token_price: Balance,
+        amount: u128,
+        amount_claimed: u128,
+        start: u64,
+        duration: u64,
+        cliff: u64,
+        revocable: bool,
+        is_active: bool,
+    }
+
+    #[test]
+    fn test_migrate_from_v0() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        env::state_write(&ContractV0 {
+            owner: accounts(1).to_string(),
+            recipient: accounts(3).to_string(),
+            token: accounts(2).to_string(),
+            token_price: 5,
+            amount: TOTAL_AMOUNT.0,
+            amount_claimed: TOTAL_AMOUNT.0 / 4,
+            start: JUNE_1_2021,
+            duration: TWO_YEARS,
+            cliff: JUNE_1_2021 + SIX_MONTHS,
+            revocable: true,
+            is_active: true,
+        });
+
+        let contract = Contract::migrate();
+        assert_eq!(contract.owner(), accounts(1).to_string());
+        assert_eq!(contract.recipient(), accounts(3).to_string());
+        assert_eq!(contract.token(), accounts(2).to_string());
+        assert_eq!(contract.token_price(), U128(5));
+        assert_eq!(contract.amount(), TOTAL_AMOUNT);
+        assert_eq!(contract.amount_claimed(), U128(TOTAL_AMOUNT.0 / 4));
+        assert_eq!(contract.start(), U64::from(JUNE_1_2021));
+        assert_eq!(contract.duration(), U64::from(TWO_YEARS));
+        assert_eq!(contract.cliff(), U64::from(JUNE_1_2021 + SIX_MONTHS));
+        assert_eq!(contract.revocable(), true);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_OWNER")]
+    fn test_migrate_not_owner() {
+        let (mut context, contract) = setup_contract();
+        env::state_write(&contract);
+        testing_env!(context
+            .predecessor_account_id(accounts(4))
+            .build()
+        );
+        Contract::migrate();
+    }
 }
@@ -0,0 +1,645 @@
+/*!
+Staking for Realities.
+NOTES:
+  - Accounts stake by sending tokens with `ft_transfer_call` on the stake token contract. Every
+    staked token is one share of the reward pool.
+  - The owner funds the rewards by sending reward tokens with `ft_transfer_call` and the
+    `fund_rewards` message, and sets how many are distributed per second. Rewards are shared
+    pro rata to the stake, using a reward per share accumulator, until the funded rewards run out.
+  - `unstake` stops the stake from earning rewards right away, but the tokens can only be
+    withdrawn with `withdraw_unstaked` after the unbonding period. An account can have at most
+    `MAX_UNBONDINGS` pending unstakes, which keeps its storage bounded.
+  - Each staking account takes `STORAGE_PER_ACCOUNT` from its storage balance, which has to be
+    paid with `storage_deposit` before the first stake. It is credited back when the account is
+    removed, once it holds nothing.
+  - Payouts are `ft_transfer` calls, so stakers need to be registered with both tokens. A failed
+    payout is credited back and can be retried.
+  - The owner upgrades the code with `upgrade`, see `upgrade.rs`.
+*/
+use near_contract_standards::fungible_token::core::ext_ft_core;
+use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{
+    assert_one_yocto, env, near_bindgen, require, AccountId, Balance, BorshStorageKey, Gas,
+    PanicOnDefault, Promise, PromiseOrValue, PromiseResult,
+};
+
+use crate::math::U256;
+
+mod math;
+mod upgrade;
+
+const GAS_FOR_FT_TRANSFER: Gas = Gas(10_000_000_000_000);
+const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas(10_000_000_000_000);
+
+const NANOSECONDS_PER_SECOND: u128 = 1_000_000_000;
+/// Scale of the reward per share accumulator.
+const PRECISION: u128 = 1_000_000_000_000_000_000_000_000;
+
+const FUND_REWARDS_MSG: &str = "fund_rewards";
+
+/// Maximum number of pending unbondings per account.
+const MAX_UNBONDINGS: usize = 10;
+/// Storage deposit each staking account takes from its storage balance, enough for the account
+/// with `MAX_UNBONDINGS` unbondings.
+const STORAGE_PER_ACCOUNT: Balance = 1_000 * env::STORAGE_PRICE_PER_BYTE;
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey {
+    Accounts,
+    StorageDeposits,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Unbonding {
+    pub amount: U128,
+    /// Timestamp the amount can be withdrawn from, in nanoseconds.
+    pub unlocks_at: U64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct Account {
+    staked: Balance,
+    /// Reward per share the rewards of the account were last computed at.
+    reward_per_share_paid: U256,
+    /// Rewards earned until `reward_per_share_paid` that weren't claimed yet.
+    rewards: Balance,
+    unbondings: Vec<Unbonding>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountView {
+    pub staked: U128,
+    pub pending_rewards: U128,
+    pub unbondings: Vec<Unbonding>,
+}
+
+/// Everything needed to compute the APR: `reward_rate_per_second * seconds per year /
+/// total_staked`, as long as `undistributed_rewards` lasts.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StakingInfo {
+    pub stake_token_id: AccountId,
+    pub reward_token_id: AccountId,
+    pub total_staked: U128,
+    pub reward_rate_per_second: U128,
+    pub undistributed_rewards: U128,
+    pub unbonding_period: U64,
+}
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    owner_id: AccountId,
+    stake_token_id: AccountId,
+    reward_token_id: AccountId,
+    /// Delay between `unstake` and `withdraw_unstaked`, in nanoseconds.
+    unbonding_period: u64,
+    reward_rate_per_second: Balance,
+    /// Funded rewards that weren't distributed yet.
+    undistributed_rewards: Balance,
+    total_staked: Balance,
+    /// Rewards distributed per staked token since the start, scaled by `PRECISION`.
+    reward_per_share: U256,
+    /// Timestamp `reward_per_share` was last updated at, in nanoseconds.
+    last_update: u64,
+    accounts: LookupMap<AccountId, Account>,
+    /// Account -> storage balance it paid that its staking account doesn't use.
+    storage_deposits: LookupMap<AccountId, Balance>,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(
+        owner_id: AccountId,
+        stake_token_id: AccountId,
+        reward_token_id: AccountId,
+        unbonding_period: U64,
+    ) -> Self {
+        require!(!env::state_exists(), "Already initialized");
+        Self {
+            owner_id,
+            stake_token_id,
+            reward_token_id,
+            unbonding_period: unbonding_period.0,
+            reward_rate_per_second: 0,
+            undistributed_rewards: 0,
+            total_staked: 0,
+            reward_per_share: U256::zero(),
+            last_update: env::block_timestamp(),
+            accounts: LookupMap::new(StorageKey::Accounts),
+            storage_deposits: LookupMap::new(StorageKey::StorageDeposits),
+        }
+    }
+
+    /// Sets the amount of reward tokens distributed per second. Only the owner can call this.
+    #[payable]
+    pub fn set_reward_rate(&mut self, reward_rate_per_second: U128) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.internal_update_reward_per_share();
+        self.reward_rate_per_second = reward_rate_per_second.0;
+    }
+
+    /// Sets the unbonding period of future unstakes. Only the owner can call this.
+    #[payable]
+    pub fn set_unbonding_period(&mut self, unbonding_period: U64) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.unbonding_period = unbonding_period.0;
+    }
+
+    /// Stops `amount` of the caller's stake from earning rewards. The tokens can be withdrawn
+    /// with `withdraw_unstaked` after the unbonding period.
+    #[payable]
+    pub fn unstake(&mut self, amount: U128) {
+        assert_one_yocto();
+        require!(amount.0 > 0, "The amount should be a positive number");
+        let account_id = env::predecessor_account_id();
+        let mut account = self.internal_get_account(&account_id);
+        require!(amount.0 <= account.staked, "The amount exceeds the stake");
+        require!(
+            account.unbondings.len() < MAX_UNBONDINGS,
+            format!("An account can't have more than {} pending unbondings", MAX_UNBONDINGS)
+        );
+        self.internal_update_account_rewards(&mut account);
+        account.staked -= amount.0;
+        self.total_staked -= amount.0;
+        account.unbondings.push(Unbonding {
+            amount,
+            unlocks_at: U64(env::block_timestamp() + self.unbonding_period),
+        });
+        self.accounts.insert(&account_id, &account);
+    }
+
+    /// Transfers every unstaked amount that finished unbonding back to the caller. Returns the
+    /// amount transferred.
+    #[payable]
+    pub fn withdraw_unstaked(&mut self) -> U128 {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let mut account = self.internal_get_account(&account_id);
+        let timestamp = env::block_timestamp();
+        let (unlocked, unbonding): (Vec<Unbonding>, Vec<Unbonding>) = account
+            .unbondings
+            .into_iter()
+            .partition(|unbonding| unbonding.unlocks_at.0 <= timestamp);
+        let amount: Balance = unlocked.iter().map(|unbonding| unbonding.amount.0).sum();
+        require!(amount > 0, "Nothing to withdraw");
+        account.unbondings = unbonding;
+        self.internal_save_account(&account_id, account);
+        self.internal_transfer(self.stake_token_id.clone(), account_id, amount, false);
+        amount.into()
+    }
+
+    /// Transfers the caller's pending rewards. Returns the amount transferred.
+    #[payable]
+    pub fn claim_rewards(&mut self) -> U128 {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let mut account = self.internal_get_account(&account_id);
+        self.internal_update_account_rewards(&mut account);
+        let amount = std::mem::take(&mut account.rewards);
+        require!(amount > 0, "No rewards to claim");
+        self.internal_save_account(&account_id, account);
+        self.internal_transfer(self.reward_token_id.clone(), account_id, amount, true);
+        amount.into()
+    }
+
+    /// Credits a failed payout back to the account.
+    #[private]
+    pub fn resolve_transfer(&mut self, account_id: AccountId, amount: U128, is_reward: bool) {
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            return;
+        }
+        let mut account = self.accounts.get(&account_id).unwrap_or_else(|| {
+            // The account was removed after the payout and takes its storage back. The payout
+            // can't be lost, so this doesn't fail when the storage balance was withdrawn since.
+            let storage_balance = self.storage_deposits.get(&account_id).unwrap_or(0);
+            self.storage_deposits
+                .insert(&account_id, &storage_balance.saturating_sub(STORAGE_PER_ACCOUNT));
+            Account::default()
+        });
+        if is_reward {
+            account.rewards += amount.0;
+        } else {
+            // Merged into an unlocked unbonding if there is one, so a failed payout doesn't take
+            // up a new entry.
+            let timestamp = env::block_timestamp();
+            match account
+                .unbondings
+                .iter_mut()
+                .find(|unbonding| unbonding.unlocks_at.0 <= timestamp)
+            {
+                Some(unbonding) => unbonding.amount = U128(unbonding.amount.0 + amount.0),
+                None => account.unbondings.push(Unbonding { amount, unlocks_at: U64(timestamp) }),
+            }
+        }
+        self.accounts.insert(&account_id, &account);
+    }
+
+    /// Adds the attached deposit to the storage balance of `account_id`, or of the caller, which
+    /// pays for its staking account.
+    #[payable]
+    pub fn storage_deposit(&mut self, account_id: Option<AccountId>) {
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        let deposit = env::attached_deposit();
+        require!(
+            deposit >= STORAGE_PER_ACCOUNT,
+            format!("Requires a minimum deposit of {}", STORAGE_PER_ACCOUNT)
+        );
+        let balance = self.storage_deposits.get(&account_id).unwrap_or(0);
+        self.storage_deposits.insert(&account_id, &(balance + deposit));
+    }
+
+    /// Withdraws the storage balance of the caller that its staking account doesn't use.
+    #[payable]
+    pub fn storage_withdraw(&mut self) -> U128 {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let amount = self.storage_deposits.remove(&account_id).unwrap_or(0);
+        if amount > 0 {
+            Promise::new(account_id).transfer(amount);
+        }
+        amount.into()
+    }
+
+    /// Returns the storage deposit a staking account needs.
+    pub fn storage_minimum_balance(&self) -> U128 {
+        U128(STORAGE_PER_ACCOUNT)
+    }
+
+    /// Returns the storage balance of `account_id` that its staking account doesn't use.
+    pub fn storage_balance_of(&self, account_id: AccountId) -> U128 {
+        U128(self.storage_deposits.get(&account_id).unwrap_or(0))
+    }
+
+    pub fn get_staking_info(&self) -> StakingInfo {
+        StakingInfo {
+            stake_token_id: self.stake_token_id.clone(),
+            reward_token_id: self.reward_token_id.clone(),
+            total_staked: self.total_staked.into(),
+            reward_rate_per_second: self.reward_rate_per_second.into(),
+            undistributed_rewards: self.undistributed_rewards.into(),
+            unbonding_period: self.unbonding_period.into(),
+        }
+    }
+
+    pub fn get_account(&self, account_id: AccountId) -> Option<AccountView> {
+        self.accounts.get(&account_id).map(|account| AccountView {
+            staked: account.staked.into(),
+            pending_rewards: self.internal_pending_rewards(&account).into(),
+            unbondings: account.unbondings,
+        })
+    }
+
+    pub fn get_pending_rewards(&self, account_id: AccountId) -> U128 {
+        self.accounts
+            .get(&account_id)
+            .map(|account| self.internal_pending_rewards(&account))
+            .unwrap_or(0)
+            .into()
+    }
+}
+
+#[near_bindgen]
+impl FungibleTokenReceiver for Contract {
+    /// Stakes the tokens for `sender_id`, or adds them to the rewards if `msg` is
+    /// `fund_rewards` and the owner sends reward tokens.
+    fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        let token_id = env::predecessor_account_id();
+        if msg == FUND_REWARDS_MSG {
+            require!(token_id == self.reward_token_id, "The token is not the reward token");
+            require!(sender_id == self.owner_id, "Only the owner can fund the rewards");
+            self.internal_update_reward_per_share();
+            self.undistributed_rewards += amount.0;
+        } else {
+            require!(token_id == self.stake_token_id, "The token is not the stake token");
+            let mut account = self.accounts.get(&sender_id).unwrap_or_else(|| {
+                let storage_balance = self.storage_deposits.get(&sender_id).unwrap_or(0);
+                require!(
+                    storage_balance >= STORAGE_PER_ACCOUNT,
+                    format!(
+                        "The account needs a storage balance of {} to stake",
+                        STORAGE_PER_ACCOUNT
+                    )
+                );
+                self.storage_deposits.insert(&sender_id, &(storage_balance - STORAGE_PER_ACCOUNT));
+                Account::default()
+            });
+            self.internal_update_account_rewards(&mut account);
+            account.staked += amount.0;
+            self.total_staked += amount.0;
+            self.accounts.insert(&sender_id, &account);
+        }
+        PromiseOrValue::Value(U128(0))
+    }
+}
+
+impl Contract {
+    fn assert_owner(&self) {
+        require!(
+            env::predecessor_account_id() == self.owner_id,
+            "Only the owner can call this method"
+        );
+    }
+
+    fn internal_get_account(&self, account_id: &AccountId) -> Account {
+        self.accounts.get(account_id).unwrap_or_else(|| env::panic_str("The account doesn't stake"))
+    }
+
+    /// Saves the account, or removes it once it holds nothing and credits its storage back.
+    fn internal_save_account(&mut self, account_id: &AccountId, account: Account) {
+        if account.staked == 0 && account.rewards == 0 && account.unbondings.is_empty() {
+            self.accounts.remove(account_id);
+            let storage_balance = self.storage_deposits.get(account_id).unwrap_or(0);
+            self.storage_deposits.insert(account_id, &(storage_balance + STORAGE_PER_ACCOUNT));
+        } else {
+            self.accounts.insert(account_id, &account);
+        }
+    }
+
+    /// Returns the reward per share at the current block timestamp and the rewards distributed
+    /// since the last update.
+    fn internal_current_reward_per_share(&self) -> (U256, Balance) {
+        let timestamp = env::block_timestamp();
+        if self.total_staked == 0 || timestamp <= self.last_update {
+            return (self.reward_per_share, 0);
+        }
+        // A 24 decimals rate times nanoseconds overflows a `u128` within days
+        let elapsed = U256::from(timestamp - self.last_update);
+        let distributed = std::cmp::min(
+            U256::from(self.reward_rate_per_second) * elapsed / U256::from(NANOSECONDS_PER_SECOND),
+            U256::from(self.undistributed_rewards),
+        )
+        .as_u128();
+        let reward_per_share = self.reward_per_share
+            + U256::from(distributed) * U256::from(PRECISION) / U256::from(self.total_staked);
+        (reward_per_share, distributed)
+    }
+
+    fn internal_update_reward_per_share(&mut self) {
+        let (reward_per_share, distributed) = self.internal_current_reward_per_share();
+        self.reward_per_share = reward_per_share;
+        self.undistributed_rewards -= distributed;
+        self.last_update = env::block_timestamp();
+    }
+
+    fn internal_earned(account: &Account, reward_per_share: U256) -> Balance {
+        let earned = U256::from(account.staked)
+            * (reward_per_share - account.reward_per_share_paid)
+            / U256::from(PRECISION);
+        account.rewards + earned.as_u128()
+    }
+
+    fn internal_pending_rewards(&self, account: &Account) -> Balance {
+        Self::internal_earned(account, self.internal_current_reward_per_share().0)
+    }
+
+    /// Moves the rewards the account earned so far into `account.rewards`. Has to run before the
+    /// stake of the account changes.
+    fn internal_update_account_rewards(&mut self, account: &mut Account) {
+        self.internal_update_reward_per_share();
+        account.rewards = Self::internal_earned(account, self.reward_per_share);
+        account.reward_per_share_paid = self.reward_per_share;
+    }
+
+    fn internal_transfer(
+        &mut self,
+        token_id: AccountId,
+        account_id: AccountId,
+        amount: Balance,
+        is_reward: bool,
+    ) {
+        ext_ft_core::ext(token_id)
+            .with_attached_deposit(1)
+            .with_static_gas(GAS_FOR_FT_TRANSFER)
+            .ft_transfer(account_id.clone(), amount.into(), None)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_TRANSFER)
+                    .resolve_transfer(account_id, amount.into(), is_reward),
+            );
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    const ONE_SECOND: u64 = 1_000_000_000;
+    const UNBONDING_PERIOD: u64 = 100 * ONE_SECOND;
+
+    fn get_context(predecessor_account_id: AccountId) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .signer_account_id(predecessor_account_id.clone())
+            .predecessor_account_id(predecessor_account_id);
+        builder
+    }
+
+    /// `accounts(1)` owns the contract, which stakes the tokens of `accounts(2)` and rewards with
+    /// the tokens of `accounts(3)`. 10 reward tokens per second are funded for 100 seconds.
+    fn setup_contract() -> (VMContextBuilder, Contract) {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.block_timestamp(0).build());
+        let mut contract =
+            Contract::new(accounts(1), accounts(2), accounts(3), U64(UNBONDING_PERIOD));
+        testing_env!(context.attached_deposit(1).build());
+        contract.set_reward_rate(U128(10));
+        testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(3)).build());
+        let _ = contract.ft_on_transfer(accounts(1), U128(1_000), FUND_REWARDS_MSG.to_string());
+        (context, contract)
+    }
+
+    /// Stakes `amount` for `account_id`, paying for its storage first if it doesn't stake yet.
+    fn stake(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        account_id: AccountId,
+        amount: Balance,
+    ) {
+        if contract.get_account(account_id.clone()).is_none() {
+            testing_env!(context
+                .attached_deposit(STORAGE_PER_ACCOUNT)
+                .predecessor_account_id(account_id.clone())
+                .build());
+            contract.storage_deposit(None);
+        }
+        testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(2)).build());
+        let _ = contract.ft_on_transfer(account_id, U128(amount), "".to_string());
+    }
+
+    #[test]
+    fn test_rewards_are_shared_pro_rata() {
+        let (mut context, mut contract) = setup_contract();
+        stake(&mut context, &mut contract, accounts(4), 100);
+
+        // accounts(4) earns everything for 10 seconds, then a third once accounts(5) stakes
+        testing_env!(context.block_timestamp(10 * ONE_SECOND).build());
+        stake(&mut context, &mut contract, accounts(5), 200);
+        testing_env!(context.block_timestamp(40 * ONE_SECOND).build());
+        assert_eq!(contract.get_pending_rewards(accounts(4)).0, 100 + 100);
+        assert_eq!(contract.get_pending_rewards(accounts(5)).0, 200);
+        assert_eq!(contract.get_staking_info().total_staked.0, 300);
+
+        // the funded rewards run out after 100 seconds
+        testing_env!(context.block_timestamp(500 * ONE_SECOND).build());
+        assert_eq!(contract.get_pending_rewards(accounts(4)).0, 100 + 300);
+        assert_eq!(contract.get_pending_rewards(accounts(5)).0, 600);
+    }
+
+    #[test]
+    fn test_claim_rewards_after_weeks() {
+        // 1 reward token per second with 24 decimals, funded for 30 days and claimed after 4 weeks
+        const ONE_TOKEN: Balance = 1_000_000_000_000_000_000_000_000;
+        const ONE_DAY: u64 = 86_400 * ONE_SECOND;
+        let mut context = get_context(accounts(1));
+        testing_env!(context.block_timestamp(0).build());
+        let mut contract =
+            Contract::new(accounts(1), accounts(2), accounts(3), U64(UNBONDING_PERIOD));
+        testing_env!(context.attached_deposit(1).build());
+        contract.set_reward_rate(U128(ONE_TOKEN));
+        testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(3)).build());
+        let funded = ONE_TOKEN * 86_400 * 30;
+        let _ = contract.ft_on_transfer(accounts(1), U128(funded), FUND_REWARDS_MSG.to_string());
+        stake(&mut context, &mut contract, accounts(4), 1_000 * ONE_TOKEN);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(4))
+            .attached_deposit(1)
+            .block_timestamp(28 * ONE_DAY)
+            .build());
+        let earned = ONE_TOKEN * 86_400 * 28;
+        assert_eq!(contract.claim_rewards().0, earned);
+        assert_eq!(contract.get_staking_info().undistributed_rewards.0, funded - earned);
+    }
+
+    #[test]
+    fn test_claim_rewards() {
+        let (mut context, mut contract) = setup_contract();
+        stake(&mut context, &mut contract, accounts(4), 100);
+        testing_env!(context
+            .predecessor_account_id(accounts(4))
+            .attached_deposit(1)
+            .block_timestamp(20 * ONE_SECOND)
+            .build());
+        assert_eq!(contract.claim_rewards().0, 200);
+        assert_eq!(contract.get_pending_rewards(accounts(4)).0, 0);
+        assert_eq!(contract.get_staking_info().undistributed_rewards.0, 800);
+    }
+
+    #[test]
+    fn test_unstake_and_withdraw() {
+        let (mut context, mut contract) = setup_contract();
+        stake(&mut context, &mut contract, accounts(4), 100);
+        testing_env!(context
+            .predecessor_account_id(accounts(4))
+            .attached_deposit(1)
+            .block_timestamp(10 * ONE_SECOND)
+            .build());
+        contract.unstake(U128(40));
+        let account = contract.get_account(accounts(4)).unwrap();
+        assert_eq!(account.staked.0, 60);
+        assert_eq!(account.pending_rewards.0, 100);
+        assert_eq!(account.unbondings[0].unlocks_at.0, 10 * ONE_SECOND + UNBONDING_PERIOD);
+
+        testing_env!(context.block_timestamp(10 * ONE_SECOND + UNBONDING_PERIOD).build());
+        assert_eq!(contract.withdraw_unstaked().0, 40);
+        assert!(contract.get_account(accounts(4)).unwrap().unbondings.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "The account needs a storage balance")]
+    fn test_stake_without_storage_deposit() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        let _ = contract.ft_on_transfer(accounts(4), U128(100), "".to_string());
+    }
+
+    #[test]
+    fn test_storage_credited_back() {
+        let (mut context, mut contract) = setup_contract();
+        stake(&mut context, &mut contract, accounts(4), 100);
+        assert_eq!(contract.storage_balance_of(accounts(4)).0, 0);
+        testing_env!(context
+            .predecessor_account_id(accounts(4))
+            .attached_deposit(1)
+            .block_timestamp(10 * ONE_SECOND)
+            .build());
+        contract.unstake(U128(100));
+        contract.claim_rewards();
+        testing_env!(context.block_timestamp(10 * ONE_SECOND + UNBONDING_PERIOD).build());
+        contract.withdraw_unstaked();
+        assert!(contract.get_account(accounts(4)).is_none());
+        assert_eq!(contract.storage_balance_of(accounts(4)).0, STORAGE_PER_ACCOUNT);
+    }
+
+    #[test]
+    #[should_panic(expected = "Nothing to withdraw")]
+    fn test_withdraw_before_unbonding() {
+        let (mut context, mut contract) = setup_contract();
+        stake(&mut context, &mut contract, accounts(4), 100);
+        testing_env!(context
+            .predecessor_account_id(accounts(4))
+            .attached_deposit(1)
+            .block_timestamp(10 * ONE_SECOND)
+            .build());
+        contract.unstake(U128(100));
+        testing_env!(context.block_timestamp(10 * ONE_SECOND + UNBONDING_PERIOD - 1).build());
+        contract.withdraw_unstaked();
+    }
+
+    #[test]
+    #[should_panic(expected = "An account can't have more than 10 pending unbondings")]
+    fn test_unstake_too_many_unbondings() {
+        let (mut context, mut contract) = setup_contract();
+        stake(&mut context, &mut contract, accounts(4), 100);
+        testing_env!(context.predecessor_account_id(accounts(4)).attached_deposit(1).build());
+        for _ in 0..=MAX_UNBONDINGS {
+            contract.unstake(U128(1));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can fund the rewards")]
+    fn test_fund_rewards_not_owner() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        let _ = contract.ft_on_transfer(accounts(4), U128(1_000), FUND_REWARDS_MSG.to_string());
+    }
+
+    #[test]
+    fn test_migrate() {
+        let (mut context, mut contract) = setup_contract();
+        stake(&mut context, &mut contract, accounts(4), 100);
+        env::state_write(&contract);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let contract = Contract::migrate();
+        assert_eq!(contract.get_staking_info().total_staked.0, 100);
+        assert_eq!(contract.get_account(accounts(4)).unwrap().staked.0, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_upgrade_not_owner() {
+        let (mut context, mut contract) = setup_contract();
+        testing_env!(context.predecessor_account_id(accounts(4)).attached_deposit(1).build());
+        contract.upgrade();
+    }
+}
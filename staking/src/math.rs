@@ -0,0 +1,21 @@
+#![allow(clippy::all)]
+//! 256 bits integers for the reward per share accumulator, which is scaled by `PRECISION` and
+//! doesn't fit in a `u128` when little is staked.
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use std::io;
+
+uint::construct_uint! {
+    pub struct U256(4);
+}
+
+impl BorshSerialize for U256 {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.0.serialize(writer)
+    }
+}
+
+impl BorshDeserialize for U256 {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        Ok(U256(<[u64; 4]>::deserialize(buf)?))
+    }
+}
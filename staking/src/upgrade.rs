@@ -0,0 +1,82 @@
+//! Code upgrades and state migrations.
+//!
+//! The owner calls `upgrade` with the new wasm as the raw input of the call. It deploys the code
+//! and calls `migrate`, which reads the state with `VersionedContract` and rewrites it in the
+//! current layout. Accounts are stored apart from `Contract`, so a change to `Account` needs its
+//! own migration.
+use crate::*;
+
+/// Gas kept by `upgrade` for itself, the rest of the prepaid gas goes to `migrate`.
+const GAS_FOR_UPGRADE: Gas = Gas(10_000_000_000_000);
+
+/// `upgrade` refuses to deploy unless at least this much gas is left for `migrate`.
+const MIN_GAS_FOR_MIGRATE: Gas = Gas(20_000_000_000_000);
+
+const STATE_KEY: &[u8] = b"STATE";
+
+pub enum VersionedContract {
+    Current(Contract),
+}
+
+impl VersionedContract {
+    /// Reads the stored state, trying the layouts from the newest to the oldest.
+    fn read() -> Self {
+        let state = env::storage_read(STATE_KEY)
+            .unwrap_or_else(|| env::panic_str("The contract is not initialized"));
+        if let Ok(contract) = Contract::try_from_slice(&state) {
+            return Self::Current(contract);
+        }
+        env::panic_str("The state has an unknown layout")
+    }
+
+    fn owner_id(&self) -> &AccountId {
+        match self {
+            Self::Current(contract) => &contract.owner_id,
+        }
+    }
+}
+
+impl From<VersionedContract> for Contract {
+    fn from(state: VersionedContract) -> Self {
+        match state {
+            VersionedContract::Current(contract) => contract,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Rewrites the stored state in the current layout. Called by `upgrade` after deploying new
+    /// code, or by the owner directly.
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let state = VersionedContract::read();
+        let predecessor_id = env::predecessor_account_id();
+        require!(
+            predecessor_id == env::current_account_id() || &predecessor_id == state.owner_id(),
+            "Only the owner can call this method"
+        );
+        state.into()
+    }
+
+    /// Deploys the wasm passed as the raw input of the call and migrates the state to it. Only
+    /// the owner can call this.
+    #[payable]
+    pub fn upgrade(&mut self) -> Promise {
+        assert_one_yocto();
+        self.assert_owner();
+        let code = env::input().unwrap_or_else(|| env::panic_str("The code is missing"));
+        let migrate_gas =
+            env::prepaid_gas().0.saturating_sub(env::used_gas().0 + GAS_FOR_UPGRADE.0);
+        require!(
+            migrate_gas >= MIN_GAS_FOR_MIGRATE.0,
+            "Not enough gas attached to migrate the state"
+        );
+        Promise::new(env::current_account_id()).deploy_contract(code).function_call(
+            "migrate".to_string(),
+            Vec::new(),
+            0,
+            Gas(migrate_gas),
+        )
+    }
+}
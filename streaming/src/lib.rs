@@ -0,0 +1,666 @@
+/*!
+Token streaming for Realities.
+NOTES:
+  - A sender opens a stream with `ft_transfer_call` on the token contract, passing the JSON
+    encoded `StreamArgs` as `msg`. The stream holds `rate_per_second * (end - start)` tokens and
+    anything sent above that is refunded right away.
+  - The tokens stream to the receiver continuously between `start` and `end`. The receiver can
+    withdraw whatever streamed so far at any time.
+  - Either party can cancel a stream. The receiver gets what streamed until then and the sender
+    gets the rest back.
+  - Payouts are `ft_transfer` calls, so both parties need to be registered with the token. A
+    failed payout is credited back to the stream and can be retried. A stream is only removed
+    once it is settled and none of its payouts is pending.
+  - Each open stream takes `STORAGE_PER_STREAM` from the storage balance of its sender, which has
+    to be paid with `storage_deposit` before the stream is opened. It is credited back when the
+    stream is removed.
+  - The owner upgrades the code with `upgrade`, see `upgrade.rs`. It has no other power over the
+    streams.
+*/
+use near_contract_standards::fungible_token::core::ext_ft_core;
+use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupMap, UnorderedSet};
+use near_sdk::json_types::U128;
+use near_sdk::{
+    assert_one_yocto, env, near_bindgen, require, serde_json, AccountId, Balance, BorshStorageKey,
+    CryptoHash, Gas, PanicOnDefault, Promise, PromiseOrValue, PromiseResult,
+};
+
+use crate::stream::{Stream, StreamArgs, StreamId};
+
+mod stream;
+mod upgrade;
+
+const GAS_FOR_FT_TRANSFER: Gas = Gas(10_000_000_000_000);
+const GAS_FOR_RESOLVE_PAYOUT: Gas = Gas(10_000_000_000_000);
+
+/// Storage deposit each open stream takes from the storage balance of its sender.
+const STORAGE_PER_STREAM: Balance = 1_000 * env::STORAGE_PRICE_PER_BYTE;
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey {
+    Streams,
+    StreamsPerAccount,
+    StreamsPerAccountInner { account_hash: CryptoHash },
+    StorageDeposits,
+}
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    /// Account allowed to upgrade the code.
+    owner_id: AccountId,
+    /// Token contract whose tokens are streamed.
+    token_id: AccountId,
+    streams: LookupMap<StreamId, Stream>,
+    /// Account -> ids of the streams it sends or receives.
+    streams_per_account: LookupMap<AccountId, UnorderedSet<StreamId>>,
+    next_stream_id: StreamId,
+    /// Account -> storage balance it paid for the streams it sends.
+    storage_deposits: LookupMap<AccountId, Balance>,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Initializes the contract to stream the tokens of `token_id`, upgraded by `owner_id`.
+    #[init]
+    pub fn new(owner_id: AccountId, token_id: AccountId) -> Self {
+        require!(!env::state_exists(), "Already initialized");
+        Self {
+            owner_id,
+            token_id,
+            streams: LookupMap::new(StorageKey::Streams),
+            streams_per_account: LookupMap::new(StorageKey::StreamsPerAccount),
+            next_stream_id: 0,
+            storage_deposits: LookupMap::new(StorageKey::StorageDeposits),
+        }
+    }
+
+    pub fn get_token_id(&self) -> AccountId {
+        self.token_id.clone()
+    }
+
+    pub fn get_owner(&self) -> AccountId {
+        self.owner_id.clone()
+    }
+
+    /// Hands the ownership over to `owner_id`. Only the current owner can call this.
+    #[payable]
+    pub fn set_owner(&mut self, owner_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.owner_id = owner_id;
+    }
+
+    /// Withdraws `amount`, or everything that streamed so far, to the receiver of the stream.
+    /// Only the receiver can call this.
+    #[payable]
+    pub fn withdraw_from_stream(&mut self, stream_id: StreamId, amount: Option<U128>) -> U128 {
+        assert_one_yocto();
+        let mut stream = self.internal_get_stream(stream_id);
+        require!(
+            env::predecessor_account_id() == stream.receiver_id,
+            "Only the receiver can withdraw from the stream"
+        );
+        let withdrawable = stream.withdrawable_at(env::block_timestamp());
+        let amount = amount.map(|amount| amount.0).unwrap_or(withdrawable);
+        require!(amount > 0, "Nothing to withdraw");
+        require!(amount <= withdrawable, "The amount exceeds the withdrawable amount");
+        stream.withdrawn = U128(stream.withdrawn.0 + amount);
+        let receiver_id = stream.receiver_id.clone();
+        self.internal_payout(&mut stream, receiver_id, amount);
+        self.streams.insert(&stream_id, &stream);
+        amount.into()
+    }
+
+    /// Stops the stream. The receiver is paid what streamed so far and the sender gets the rest
+    /// back. Either of them can call this.
+    #[payable]
+    pub fn cancel_stream(&mut self, stream_id: StreamId) {
+        assert_one_yocto();
+        let mut stream = self.internal_get_stream(stream_id);
+        let account_id = env::predecessor_account_id();
+        require!(
+            account_id == stream.sender_id || account_id == stream.receiver_id,
+            "Only the sender or the receiver can cancel the stream"
+        );
+        let timestamp = env::block_timestamp();
+        let streamed = stream.streamed_at(timestamp);
+        let refund = stream.deposit.0 - streamed;
+        let withdrawable = streamed - stream.withdrawn.0;
+        stream.end.0 = std::cmp::min(timestamp, stream.end.0);
+        stream.start.0 = std::cmp::min(stream.start.0, stream.end.0);
+        stream.deposit = U128(streamed);
+        stream.withdrawn = U128(streamed);
+        if withdrawable > 0 {
+            let receiver_id = stream.receiver_id.clone();
+            self.internal_payout(&mut stream, receiver_id, withdrawable);
+        }
+        if refund > 0 {
+            let sender_id = stream.sender_id.clone();
+            self.internal_payout(&mut stream, sender_id, refund);
+        }
+        if stream.pending_payouts == 0 {
+            self.internal_remove_stream(&stream);
+        } else {
+            self.streams.insert(&stream_id, &stream);
+        }
+    }
+
+    /// Credits a failed payout back to the stream, and removes the stream once it is settled and
+    /// none of its payouts is pending.
+    #[private]
+    pub fn resolve_payout(&mut self, stream_id: StreamId, account_id: AccountId, amount: U128) {
+        // The stream is kept while it has pending payouts, so it always exists here.
+        let mut stream = self.internal_get_stream(stream_id);
+        stream.pending_payouts -= 1;
+        if !matches!(env::promise_result(0), PromiseResult::Successful(_)) {
+            if account_id == stream.receiver_id {
+                stream.withdrawn = U128(stream.withdrawn.0 - amount.0);
+            } else {
+                // The sender's refund stays in the stream until it cancels again.
+                stream.deposit = U128(stream.deposit.0 + amount.0);
+            }
+        }
+        if stream.pending_payouts == 0 && stream.is_settled(env::block_timestamp()) {
+            self.internal_remove_stream(&stream);
+        } else {
+            self.streams.insert(&stream_id, &stream);
+        }
+    }
+
+    /// Adds the attached deposit to the storage balance of `account_id`, or of the caller, which
+    /// pays for the streams it opens.
+    #[payable]
+    pub fn storage_deposit(&mut self, account_id: Option<AccountId>) {
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        let deposit = env::attached_deposit();
+        require!(
+            deposit >= STORAGE_PER_STREAM,
+            format!("Requires a minimum deposit of {}", STORAGE_PER_STREAM)
+        );
+        let balance = self.storage_deposits.get(&account_id).unwrap_or(0);
+        self.storage_deposits.insert(&account_id, &(balance + deposit));
+    }
+
+    /// Withdraws the storage balance of the caller that open streams don't use.
+    #[payable]
+    pub fn storage_withdraw(&mut self) -> U128 {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let amount = self.storage_deposits.remove(&account_id).unwrap_or(0);
+        if amount > 0 {
+            Promise::new(account_id).transfer(amount);
+        }
+        amount.into()
+    }
+
+    /// Returns the storage deposit each stream needs.
+    pub fn storage_minimum_balance(&self) -> U128 {
+        U128(STORAGE_PER_STREAM)
+    }
+
+    /// Returns the storage balance of `account_id` that open streams don't use.
+    pub fn storage_balance_of(&self, account_id: AccountId) -> U128 {
+        U128(self.storage_deposits.get(&account_id).unwrap_or(0))
+    }
+
+    pub fn get_stream(&self, stream_id: StreamId) -> Option<Stream> {
+        self.streams.get(&stream_id)
+    }
+
+    /// Returns the amount the receiver of the stream can withdraw now.
+    pub fn get_withdrawable(&self, stream_id: StreamId) -> U128 {
+        self.internal_get_stream(stream_id).withdrawable_at(env::block_timestamp()).into()
+    }
+
+    /// Returns the streams `account_id` sends or receives.
+    pub fn get_streams_for_account(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<Stream> {
+        let stream_ids = match self.streams_per_account.get(&account_id) {
+            Some(stream_ids) => stream_ids,
+            None => return vec![],
+        };
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+        stream_ids
+            .iter()
+            .skip(start as usize)
+            .take(limit.unwrap_or(stream_ids.len()) as usize)
+            .filter_map(|stream_id| self.streams.get(&stream_id))
+            .collect()
+    }
+
+    pub fn get_stream_count_for_account(&self, account_id: AccountId) -> u64 {
+        self.streams_per_account.get(&account_id).map(|stream_ids| stream_ids.len()).unwrap_or(0)
+    }
+}
+
+#[near_bindgen]
+impl FungibleTokenReceiver for Contract {
+    /// Opens a stream from `sender_id` with the `StreamArgs` in `msg`, and returns the part of
+    /// `amount` the stream doesn't need.
+    fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        require!(env::predecessor_account_id() == self.token_id, "The token is not supported");
+        let args: StreamArgs =
+            serde_json::from_str(&msg).unwrap_or_else(|_| env::panic_str("Invalid stream args"));
+        require!(args.receiver_id != sender_id, "The sender can't stream to itself");
+        require!(args.end.0 > args.start.0, "The stream should end after it starts");
+        require!(args.end.0 > env::block_timestamp(), "The stream should end in the future");
+        let deposit = Stream::amount_for(args.rate_per_second.0, args.start.0, args.end.0);
+        require!(deposit > 0, "The stream should stream a positive amount");
+        require!(deposit <= amount.0, format!("The stream needs a deposit of {}", deposit));
+        let storage_balance = self.storage_deposits.get(&sender_id).unwrap_or(0);
+        require!(
+            storage_balance >= STORAGE_PER_STREAM,
+            format!(
+                "The sender needs a storage balance of {} to open a stream",
+                STORAGE_PER_STREAM
+            )
+        );
+        self.storage_deposits.insert(&sender_id, &(storage_balance - STORAGE_PER_STREAM));
+
+        let stream = Stream {
+            id: self.next_stream_id,
+            sender_id,
+            receiver_id: args.receiver_id,
+            rate_per_second: args.rate_per_second,
+            start: args.start,
+            end: args.end,
+            deposit: U128(deposit),
+            withdrawn: U128(0),
+            pending_payouts: 0,
+        };
+        self.next_stream_id += 1;
+        self.internal_add_stream(&stream);
+        PromiseOrValue::Value(U128(amount.0 - deposit))
+    }
+}
+
+impl Contract {
+    fn assert_owner(&self) {
+        require!(
+            env::predecessor_account_id() == self.owner_id,
+            "Only the owner can call this method"
+        );
+    }
+
+    fn internal_get_stream(&self, stream_id: StreamId) -> Stream {
+        self.streams.get(&stream_id).unwrap_or_else(|| env::panic_str("The stream doesn't exist"))
+    }
+
+    fn internal_add_stream(&mut self, stream: &Stream) {
+        self.streams.insert(&stream.id, stream);
+        for account_id in [&stream.sender_id, &stream.receiver_id].iter() {
+            let mut stream_ids = self.streams_per_account.get(account_id).unwrap_or_else(|| {
+                UnorderedSet::new(StorageKey::StreamsPerAccountInner {
+                    account_hash: env::sha256_array(account_id.as_bytes()),
+                })
+            });
+            stream_ids.insert(&stream.id);
+            self.streams_per_account.insert(account_id, &stream_ids);
+        }
+    }
+
+    fn internal_remove_stream(&mut self, stream: &Stream) {
+        self.streams.remove(&stream.id);
+        let storage_balance = self.storage_deposits.get(&stream.sender_id).unwrap_or(0);
+        self.storage_deposits.insert(&stream.sender_id, &(storage_balance + STORAGE_PER_STREAM));
+        for account_id in [&stream.sender_id, &stream.receiver_id].iter() {
+            if let Some(mut stream_ids) = self.streams_per_account.get(account_id) {
+                stream_ids.remove(&stream.id);
+                if stream_ids.is_empty() {
+                    self.streams_per_account.remove(account_id);
+                } else {
+                    self.streams_per_account.insert(account_id, &stream_ids);
+                }
+            }
+        }
+    }
+
+    /// Sends `amount` to `account_id` and counts the payout as pending until `resolve_payout`.
+    /// The caller has to save the stream.
+    fn internal_payout(&self, stream: &mut Stream, account_id: AccountId, amount: Balance) {
+        stream.pending_payouts += 1;
+        ext_ft_core::ext(self.token_id.clone())
+            .with_attached_deposit(1)
+            .with_static_gas(GAS_FOR_FT_TRANSFER)
+            .ft_transfer(account_id.clone(), amount.into(), Some(format!("stream {}", stream.id)))
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_PAYOUT)
+                    .resolve_payout(stream.id, account_id, amount.into()),
+            );
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, RuntimeFeesConfig, VMConfig};
+
+    use super::*;
+    use crate::upgrade::ContractV1;
+
+    const ONE_SECOND: u64 = 1_000_000_000;
+    const RATE_PER_SECOND: Balance = 1_000;
+
+    fn get_context(predecessor_account_id: AccountId) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .signer_account_id(predecessor_account_id.clone())
+            .predecessor_account_id(predecessor_account_id);
+        builder
+    }
+
+    /// Opens a 100 seconds stream from `accounts(1)` to `accounts(2)` of the token `accounts(3)`.
+    fn setup_stream() -> (VMContextBuilder, Contract) {
+        let mut context = get_context(accounts(3));
+        testing_env!(context.block_timestamp(0).build());
+        let mut contract = Contract::new(accounts(5), accounts(3));
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(STORAGE_PER_STREAM)
+            .build());
+        contract.storage_deposit(None);
+        testing_env!(context.predecessor_account_id(accounts(3)).attached_deposit(0).build());
+        let msg = serde_json::json!({
+            "receiver_id": accounts(2),
+            "rate_per_second": U128(RATE_PER_SECOND),
+            "start": (10 * ONE_SECOND).to_string(),
+            "end": (110 * ONE_SECOND).to_string(),
+        });
+        let unused =
+            contract.ft_on_transfer(accounts(1), U128(RATE_PER_SECOND * 100 + 5), msg.to_string());
+        match unused {
+            PromiseOrValue::Value(unused) => assert_eq!(unused.0, 5),
+            PromiseOrValue::Promise(_) => panic!("Expected a value"),
+        }
+        (context, contract)
+    }
+
+    #[test]
+    fn test_new_stream() {
+        let (_, contract) = setup_stream();
+        let stream = contract.get_stream(0).unwrap();
+        assert_eq!(stream.sender_id, accounts(1));
+        assert_eq!(stream.deposit.0, RATE_PER_SECOND * 100);
+        assert_eq!(contract.get_streams_for_account(accounts(1), None, None), vec![stream.clone()]);
+        assert_eq!(contract.get_streams_for_account(accounts(2), None, None), vec![stream]);
+        assert!(contract.get_streams_for_account(accounts(4), None, None).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "The sender needs a storage balance")]
+    fn test_new_stream_without_storage_deposit() {
+        let context = get_context(accounts(3));
+        testing_env!(context.build());
+        let mut contract = Contract::new(accounts(5), accounts(3));
+        let msg = serde_json::json!({
+            "receiver_id": accounts(2),
+            "rate_per_second": U128(RATE_PER_SECOND),
+            "start": "0",
+            "end": (100 * ONE_SECOND).to_string(),
+        });
+        let _ = contract.ft_on_transfer(accounts(1), U128(RATE_PER_SECOND * 100), msg.to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "The token is not supported")]
+    fn test_new_stream_wrong_token() {
+        let mut context = get_context(accounts(3));
+        testing_env!(context.build());
+        let mut contract = Contract::new(accounts(5), accounts(3));
+        testing_env!(context.predecessor_account_id(accounts(4)).build());
+        let _ = contract.ft_on_transfer(accounts(1), U128(100), "{}".to_string());
+    }
+
+    #[test]
+    fn test_withdraw_from_stream() {
+        let (mut context, mut contract) = setup_stream();
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(1)
+            .block_timestamp(5 * ONE_SECOND)
+            .build());
+        assert_eq!(contract.get_withdrawable(0).0, 0);
+
+        testing_env!(context.block_timestamp(30 * ONE_SECOND).build());
+        assert_eq!(contract.withdraw_from_stream(0, None).0, RATE_PER_SECOND * 20);
+        testing_env!(context.block_timestamp(60 * ONE_SECOND + ONE_SECOND / 2).build());
+        assert_eq!(contract.get_withdrawable(0).0, RATE_PER_SECOND * 61 / 2);
+        assert_eq!(contract.withdraw_from_stream(0, Some(U128(10))).0, 10);
+        testing_env!(context.block_timestamp(200 * ONE_SECOND).build());
+        assert_eq!(contract.get_withdrawable(0).0, RATE_PER_SECOND * 80 - 10);
+    }
+
+    #[test]
+    fn test_withdraw_from_long_stream() {
+        // 1 token per second with 24 decimals for 30 days
+        const ONE_DAY: u64 = 86_400 * ONE_SECOND;
+        let rate_per_second: Balance = 10u128.pow(24);
+        let deposit = rate_per_second * 86_400 * 30;
+        let mut context = get_context(accounts(3));
+        testing_env!(context.block_timestamp(0).build());
+        let mut contract = Contract::new(accounts(5), accounts(3));
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(STORAGE_PER_STREAM)
+            .build());
+        contract.storage_deposit(None);
+        testing_env!(context.predecessor_account_id(accounts(3)).attached_deposit(0).build());
+        let msg = serde_json::json!({
+            "receiver_id": accounts(2),
+            "rate_per_second": U128(rate_per_second),
+            "start": "0",
+            "end": (30 * ONE_DAY).to_string(),
+        });
+        let _ = contract.ft_on_transfer(accounts(1), U128(deposit), msg.to_string());
+        assert_eq!(contract.get_stream(0).unwrap().deposit.0, deposit);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(1)
+            .block_timestamp(20 * ONE_DAY + ONE_SECOND / 2)
+            .build());
+        let streamed = rate_per_second * 86_400 * 20 + rate_per_second / 2;
+        assert_eq!(contract.withdraw_from_stream(0, None).0, streamed);
+        testing_env!(context.block_timestamp(40 * ONE_DAY).build());
+        assert_eq!(contract.get_withdrawable(0).0, deposit - streamed);
+    }
+
+    #[test]
+    #[should_panic(expected = "The amount exceeds the withdrawable amount")]
+    fn test_withdraw_from_stream_too_much() {
+        let (mut context, mut contract) = setup_stream();
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(1)
+            .block_timestamp(30 * ONE_SECOND)
+            .build());
+        contract.withdraw_from_stream(0, Some(U128(RATE_PER_SECOND * 20 + 1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the receiver can withdraw from the stream")]
+    fn test_withdraw_from_stream_not_receiver() {
+        let (mut context, mut contract) = setup_stream();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .block_timestamp(30 * ONE_SECOND)
+            .build());
+        contract.withdraw_from_stream(0, None);
+    }
+
+    #[test]
+    fn test_cancel_stream() {
+        let (mut context, mut contract) = setup_stream();
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(1)
+            .block_timestamp(30 * ONE_SECOND)
+            .build());
+        contract.withdraw_from_stream(0, None);
+
+        // the sender cancels after 3/4 of the stream
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .block_timestamp(85 * ONE_SECOND)
+            .build());
+        contract.cancel_stream(0);
+        let stream = contract.get_stream(0).unwrap();
+        assert_eq!(stream.end.0, 85 * ONE_SECOND);
+        assert_eq!(stream.deposit.0, RATE_PER_SECOND * 75);
+        assert_eq!(stream.withdrawn.0, RATE_PER_SECOND * 75);
+        testing_env!(context.block_timestamp(200 * ONE_SECOND).build());
+        assert_eq!(contract.get_withdrawable(0).0, 0);
+    }
+
+    /// Runs `resolve_payout` as the callback of a payout that succeeded or failed.
+    fn resolve_payout(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        account_id: AccountId,
+        amount: Balance,
+        result: PromiseResult,
+    ) {
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).attached_deposit(0).build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![result]
+        );
+        contract.resolve_payout(0, account_id, U128(amount));
+    }
+
+    /// Cancels the stream of `setup_stream` after 3/4 of it, so the receiver is paid
+    /// `RATE_PER_SECOND * 75` and the sender gets `RATE_PER_SECOND * 25` back.
+    fn setup_cancelled_stream() -> (VMContextBuilder, Contract) {
+        let (mut context, mut contract) = setup_stream();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .block_timestamp(85 * ONE_SECOND)
+            .build());
+        contract.cancel_stream(0);
+        assert_eq!(contract.get_stream(0).unwrap().pending_payouts, 2);
+        (context, contract)
+    }
+
+    #[test]
+    fn test_resolve_payouts() {
+        let (mut context, mut contract) = setup_cancelled_stream();
+        let success = PromiseResult::Successful(vec![]);
+        resolve_payout(&mut context, &mut contract, accounts(2), RATE_PER_SECOND * 75, success);
+        assert_eq!(contract.get_stream(0).unwrap().pending_payouts, 1);
+        let success = PromiseResult::Successful(vec![]);
+        resolve_payout(&mut context, &mut contract, accounts(1), RATE_PER_SECOND * 25, success);
+        assert!(contract.get_stream(0).is_none());
+        assert!(contract.get_streams_for_account(accounts(1), None, None).is_empty());
+        assert_eq!(contract.storage_balance_of(accounts(1)).0, STORAGE_PER_STREAM);
+    }
+
+    #[test]
+    fn test_resolve_payouts_refund_failed() {
+        let (mut context, mut contract) = setup_cancelled_stream();
+        let success = PromiseResult::Successful(vec![]);
+        resolve_payout(&mut context, &mut contract, accounts(2), RATE_PER_SECOND * 75, success);
+        resolve_payout(
+            &mut context,
+            &mut contract,
+            accounts(1),
+            RATE_PER_SECOND * 25,
+            PromiseResult::Failed,
+        );
+        let stream = contract.get_stream(0).unwrap();
+        assert_eq!(stream.pending_payouts, 0);
+        assert_eq!(stream.deposit.0, RATE_PER_SECOND * 100);
+        assert_eq!(stream.withdrawn.0, RATE_PER_SECOND * 75);
+        assert_eq!(contract.get_withdrawable(0).0, 0);
+
+        // the sender retries the refund
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .block_timestamp(90 * ONE_SECOND)
+            .build());
+        contract.cancel_stream(0);
+        let success = PromiseResult::Successful(vec![]);
+        resolve_payout(&mut context, &mut contract, accounts(1), RATE_PER_SECOND * 25, success);
+        assert!(contract.get_stream(0).is_none());
+    }
+
+    #[test]
+    fn test_resolve_payouts_both_failed() {
+        let (mut context, mut contract) = setup_cancelled_stream();
+        resolve_payout(
+            &mut context,
+            &mut contract,
+            accounts(2),
+            RATE_PER_SECOND * 75,
+            PromiseResult::Failed,
+        );
+        resolve_payout(
+            &mut context,
+            &mut contract,
+            accounts(1),
+            RATE_PER_SECOND * 25,
+            PromiseResult::Failed,
+        );
+        let stream = contract.get_stream(0).unwrap();
+        assert_eq!(stream.pending_payouts, 0);
+        assert_eq!(stream.deposit.0, RATE_PER_SECOND * 100);
+        assert_eq!(stream.withdrawn.0, 0);
+        assert_eq!(contract.get_withdrawable(0).0, RATE_PER_SECOND * 75);
+        assert_eq!(contract.storage_balance_of(accounts(1)).0, 0);
+    }
+
+    #[test]
+    fn test_migrate() {
+        let (mut context, contract) = setup_stream();
+        env::state_write(&contract);
+        testing_env!(context.predecessor_account_id(accounts(5)).build());
+        let contract = Contract::migrate();
+        assert_eq!(contract.get_owner(), accounts(5));
+        assert_eq!(contract.get_token_id(), accounts(3));
+        assert_eq!(contract.get_stream(0).unwrap().deposit.0, RATE_PER_SECOND * 100);
+        assert_eq!(contract.get_stream_count_for_account(accounts(2)), 1);
+    }
+
+    #[test]
+    fn test_migrate_from_v1() {
+        let (mut context, contract) = setup_stream();
+        env::state_write(&ContractV1 {
+            token_id: contract.token_id,
+            streams: contract.streams,
+            streams_per_account: contract.streams_per_account,
+            next_stream_id: contract.next_stream_id,
+            storage_deposits: contract.storage_deposits,
+        });
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let contract = Contract::migrate();
+        assert_eq!(contract.get_owner(), accounts(0));
+        assert_eq!(contract.get_token_id(), accounts(3));
+        assert_eq!(contract.get_stream(0).unwrap().deposit.0, RATE_PER_SECOND * 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_upgrade_not_owner() {
+        let (mut context, mut contract) = setup_stream();
+        testing_env!(context.predecessor_account_id(accounts(0)).attached_deposit(1).build());
+        contract.upgrade();
+    }
+}
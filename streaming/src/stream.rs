@@ -0,0 +1,65 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{AccountId, Balance};
+
+const NANOSECONDS_PER_SECOND: u128 = 1_000_000_000;
+
+pub type StreamId = u64;
+
+/// Stream parameters passed as the `msg` of `ft_transfer_call`.
+#[derive(Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StreamArgs {
+    pub receiver_id: AccountId,
+    pub rate_per_second: U128,
+    /// Timestamp the stream starts at, in nanoseconds.
+    pub start: U64,
+    /// Timestamp the stream ends at, in nanoseconds.
+    pub end: U64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Stream {
+    pub id: StreamId,
+    pub sender_id: AccountId,
+    pub receiver_id: AccountId,
+    pub rate_per_second: U128,
+    pub start: U64,
+    /// Moved back to the cancellation time when the stream is cancelled.
+    pub end: U64,
+    /// Amount the stream holds in total, including what was already withdrawn.
+    pub deposit: U128,
+    /// Amount the receiver already withdrew.
+    pub withdrawn: U128,
+    /// Number of payouts whose `resolve_payout` callback hasn't run yet.
+    pub pending_payouts: u32,
+}
+
+impl Stream {
+    /// Amount that streamed between `start` and `end` for the given rate. Whole seconds and the
+    /// nanoseconds left are multiplied apart, since a 24 decimals rate times a duration in
+    /// nanoseconds overflows a `u128` within days.
+    pub fn amount_for(rate_per_second: Balance, start: u64, end: u64) -> Balance {
+        let elapsed = Balance::from(end.saturating_sub(start));
+        rate_per_second * (elapsed / NANOSECONDS_PER_SECOND)
+            + rate_per_second * (elapsed % NANOSECONDS_PER_SECOND) / NANOSECONDS_PER_SECOND
+    }
+
+    /// Amount streamed to the receiver by `timestamp`, withdrawn or not.
+    pub fn streamed_at(&self, timestamp: u64) -> Balance {
+        let until = std::cmp::min(timestamp, self.end.0);
+        std::cmp::min(Self::amount_for(self.rate_per_second.0, self.start.0, until), self.deposit.0)
+    }
+
+    /// Amount the receiver can withdraw at `timestamp`.
+    pub fn withdrawable_at(&self, timestamp: u64) -> Balance {
+        self.streamed_at(timestamp) - self.withdrawn.0
+    }
+
+    /// Whether the stream ended and everything was paid out.
+    pub fn is_settled(&self, timestamp: u64) -> bool {
+        timestamp >= self.end.0 && self.withdrawn == self.deposit
+    }
+}